@@ -1,14 +1,23 @@
 use std::path::PathBuf;
 
 use anyhow::Result;
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use walkdir::WalkDir;
 
 use crate::errors::Error;
 
 #[derive(Clone, Debug, Default, Parser)]
-#[command(name = "ncmdump", bin_name = "ncmdump", about, version)]
+#[command(
+    name = "ncmdump",
+    bin_name = "ncmdump",
+    about,
+    version,
+    args_conflicts_with_subcommands = true
+)]
 pub(crate) struct Command {
+    #[command(subcommand)]
+    pub(crate) subcommand: Option<SubCommand>,
+
     /// Specified the files or dirs to convert.
     #[arg(value_name = "TARGETS")]
     pub(crate) targets: Vec<PathBuf>,
@@ -35,6 +44,26 @@ pub(crate) struct Command {
     /// It should more than 0 and less than 9.
     #[arg(short = 'w', long = "worker", default_value = "1")]
     pub(crate) worker: usize,
+
+    /// The uuid of device which downloaded the joox files.
+    /// It's required for the `.ofl_en` file.
+    #[arg(long = "joox-uuid", value_name = "UUID")]
+    pub(crate) joox_uuid: Option<String>,
+
+    /// The key database of kugou client, which is exported as json from `KGMusicV3.db`.
+    /// It's required for the `.kgg` file.
+    #[arg(long = "kgg-db", value_name = "FILE")]
+    pub(crate) kgg_db: Option<PathBuf>,
+}
+
+#[derive(Clone, Debug, Subcommand)]
+pub(crate) enum SubCommand {
+    /// Print the layout of ncm header, which is the offset and the length of every section.
+    Inspect {
+        /// Specified the ncm files to inspect.
+        #[arg(value_name = "FILES", required = true)]
+        files: Vec<PathBuf>,
+    },
 }
 
 impl Command {
@@ -93,7 +122,9 @@ mod tests {
     use std::path::PathBuf;
 
     use anyhow::Result;
+    use clap::Parser;
 
+    use crate::command::SubCommand;
     use crate::errors::Error;
     use crate::Command;
 
@@ -144,4 +175,22 @@ mod tests {
         }
         Ok(())
     }
+
+    #[test]
+    fn test_parse_inspect_ok() -> Result<()> {
+        let command = Command::try_parse_from(["ncmdump", "inspect", "test.ncm"])?;
+        assert!(command.targets.is_empty());
+        assert!(matches!(
+            command.subcommand,
+            Some(SubCommand::Inspect { files }) if files == vec![PathBuf::from("test.ncm")]
+        ));
+
+        let command = Command::try_parse_from(["ncmdump", "-v", "test.ncm"])?;
+        assert!(command.subcommand.is_none());
+        assert_eq!(command.targets, vec![PathBuf::from("test.ncm")]);
+
+        let result = Command::try_parse_from(["ncmdump", "inspect"]);
+        assert!(result.is_err());
+        Ok(())
+    }
 }
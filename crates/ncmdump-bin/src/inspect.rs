@@ -0,0 +1,61 @@
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use ncmdump::{NcmDump, NcmHeader};
+
+/// Print the layout of ncm header for every file.
+pub(crate) fn inspect(files: &[PathBuf]) -> Result<()> {
+    let mut stdout = io::stdout().lock();
+    for path in files {
+        match read_header(path) {
+            Ok(header) => write_header(&mut stdout, path, &header)?,
+            Err(e) => eprintln!("[Warning] {e}: {path:?}"),
+        }
+    }
+    Ok(())
+}
+
+fn read_header(path: &Path) -> Result<NcmHeader> {
+    let ncm = NcmDump::from_reader(File::open(path)?)?;
+    Ok(ncm.get_header().clone())
+}
+
+fn write_header<W>(writer: &mut W, path: &Path, header: &NcmHeader) -> Result<()>
+where
+    W: Write,
+{
+    writeln!(writer, "{}", path.display())?;
+    let sections = [
+        ("magic", &header.magic),
+        ("gap", &header.magic_gap),
+        ("key", &header.key),
+        ("info", &header.info),
+        ("crc", &header.crc),
+        ("gap", &header.crc_gap),
+        ("frame", &header.frame),
+        ("image", &header.image),
+    ];
+    for (name, range) in sections {
+        writeln!(
+            writer,
+            "  {name:<8} offset: {:#010x}  length: {}",
+            range.start,
+            range.len()
+        )?;
+    }
+    writeln!(
+        writer,
+        "  {:<8} offset: {:#010x}",
+        "audio", header.audio_offset
+    )?;
+
+    let key = header
+        .rc4_key
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect::<String>();
+    writeln!(writer, "  {:<8} {key}", "rc4 key")?;
+    Ok(())
+}
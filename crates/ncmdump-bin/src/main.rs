@@ -8,16 +8,17 @@ use anyhow::Result;
 use clap::Parser;
 
 use ncmdump::utils::FileType;
-use ncmdump::{NcmDump, QmcDump};
+use ncmdump::{Dump, Format, JooxDump, KggDump, NcmInfo, Registry};
 
-use crate::command::Command;
+use crate::command::{Command, SubCommand};
 use crate::errors::Error;
-use crate::metadata::{FlacMetadata, Metadata, Mp3Metadata};
+use crate::metadata::inject_metadata;
 use crate::provider::{DataProvider, FileProvider};
 use crate::state::State;
 
 mod command;
 mod errors;
+mod inspect;
 mod metadata;
 mod provider;
 mod state;
@@ -28,28 +29,59 @@ mod utils;
 struct Program {
     command: Arc<Command>,
     state: Arc<State>,
+    registry: Arc<Registry>,
 }
 
 impl Program {
     /// Create new command progress.
     fn new(command: Command) -> Result<Self> {
         let state = State::try_from(&command)?;
+        let mut registry = Registry::builtin();
+        if let Some(path) = &command.kgg_db {
+            let keys = ncmdump::load_kgg_keys(File::open(path)?)?;
+            registry.register(
+                Format::builtin(FileType::Kgg).with_constructor(move |reader| {
+                    Ok(Box::new(KggDump::from_reader(reader, &keys)?))
+                }),
+            );
+        }
+        if let Some(uuid) = command.joox_uuid.clone() {
+            registry.register(
+                Format::builtin(FileType::Joox).with_constructor(move |reader| {
+                    Ok(Box::new(JooxDump::from_reader(reader, &uuid)?))
+                }),
+            );
+        }
         Ok(Self {
             command: Arc::new(command),
             state: Arc::new(state),
+            registry: Arc::new(registry),
         })
     }
 
+    /// Create the dumper by the format of file.
+    fn create_dump<P>(&self, provider: &P) -> Result<Box<dyn Dump>>
+    where
+        P: DataProvider,
+    {
+        let path = provider.get_path();
+        let mut source = File::open(&path)?;
+        let extension = path.extension().and_then(|ext| ext.to_str());
+        let format = self
+            .registry
+            .detect(&mut source, extension)?
+            .ok_or(Error::Format)?;
+        let dump = format.open(source)?;
+        Ok(dump)
+    }
+
     fn dump<P>(&self, provider: &P) -> Result<()>
     where
         P: DataProvider,
     {
-        let source = File::open(provider.get_path())?;
-        let result = match provider.get_format() {
-            FileType::Ncm => self.dump_data(provider, NcmDump::from_reader(source)?),
-            FileType::Qmc => self.dump_data(provider, QmcDump::from_reader(source)?),
-            FileType::Other => Err(Error::Format.into()),
-        };
+        let result = self
+            .create_dump(provider)
+            .and_then(|dump| self.dump_data(provider, dump));
         if let Err(ref e) = result {
             self.state
                 .println(format!("[Warning] {e}: {:?}", provider.get_path()))?;
@@ -57,26 +89,30 @@ impl Program {
         Ok(())
     }
 
-    fn dump_data<R, P>(&self, provider: &P, mut source: R) -> Result<()>
+    /// Get the information from the `.idx` file beside the uc file, if it's exists.
+    fn get_index<P>(provider: &P) -> Option<NcmInfo>
+    where
+        P: DataProvider,
+    {
+        let path = provider.get_path();
+        ["idx", "idx!"]
+            .iter()
+            .map(|ext| path.with_extension(ext))
+            .find(|path| path.exists())
+            .and_then(|path| File::open(path).ok())
+            .and_then(|file| NcmInfo::from_index(file).ok())
+    }
+
+    fn dump_data<P>(&self, provider: &P, mut source: Box<dyn Dump>) -> Result<()>
     where
-        R: Read,
         P: DataProvider,
     {
+        // Get file extensions early and return quickly if formatted incorrectly
+        let format = source.get_audio_format()?.ok_or(Error::Format)?;
+
         let progress = self.state.create_progress(provider)?;
         let mut data: Cursor<Vec<u8>> = Cursor::new(Vec::new());
         let mut buffer = [0; 1024];
-        let mut ext_buffer = [0; 4];
-
-        // Get file extensions early and return quickly if formatted incorrectly
-        let ext = match source.read(&mut ext_buffer) {
-            Ok(4) => match ext_buffer {
-                [0x66, 0x4C, 0x61, 0x43] => Ok("flac"),
-                [0x49, 0x44, 0x33, _] => Ok("mp3"),
-                _ => Err(Error::Format),
-            },
-            Ok(_) => Err(Error::Format),
-            Err(e) => return Err(e.into()),
-        }?;
 
         // Get output file path
         let path = provider.get_path();
@@ -87,7 +123,9 @@ impl Program {
             )))?,
             Some(p) => Path::new(p),
         };
-        let target_path = parent.join(provider.get_name()).with_extension(ext);
+        let target_path = parent
+            .join(provider.get_name())
+            .with_extension(format.extension());
 
         // Open / Create file
         let mut option = OpenOptions::new();
@@ -98,9 +136,6 @@ impl Program {
             (true, false) => return Err(Error::Exists.into()),
         }?;
 
-        // Don't lose these 4 bits
-        data.write_all(&ext_buffer)?;
-
         // Read data
         loop {
             // Read data from dumper
@@ -126,23 +161,18 @@ impl Program {
 
         let data = data.into_inner();
 
-        match provider.get_format() {
-            FileType::Ncm => {
-                let file = File::open(provider.get_path())?;
-                let mut dump = NcmDump::from_reader(file)?;
-                let image = dump.get_image()?;
-                let info = dump.get_info()?;
-                if ext == "mp3" {
-                    let buffer = Mp3Metadata::new(&info, &image, &data).inject_metadata(data)?;
-                    target.write_all(&buffer)?;
-                } else if ext == "flac" {
-                    let buffer = FlacMetadata::new(&info, &image, &data).inject_metadata(data)?;
-                    target.write_all(&buffer)?;
-                }
-            }
-            FileType::Qmc => target.write_all(&data)?,
-            FileType::Other => return Err(Error::Format.into()),
+        // The information of uc file is saved in the index file beside it
+        let info = match source.get_info()? {
+            Some(info) => Some(info),
+            None => Self::get_index(provider),
         };
+        let images = source.get_images()?;
+        match (info, images.is_empty()) {
+            (None, true) => target.write_all(&data)?,
+            (info, _) => {
+                target.write_all(&inject_metadata(format, info.as_ref(), &images, data)?)?
+            }
+        }
 
         // Finish progress bar
         if let Some(p) = &progress {
@@ -187,6 +217,9 @@ impl Program {
 
 fn main() -> Result<()> {
     let command = Command::parse();
+    if let Some(SubCommand::Inspect { files }) = &command.subcommand {
+        return inspect::inspect(files);
+    }
     command.invalid()?;
 
     let program = Program::new(command)?;
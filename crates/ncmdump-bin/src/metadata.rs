@@ -4,7 +4,8 @@ use anyhow::Result;
 use id3::frame::Picture;
 use id3::{TagLike, Version};
 
-use ncmdump::NcmInfo;
+use ncmdump::audio::AudioFormat;
+use ncmdump::{NcmImage, NcmImageKind, NcmInfo};
 
 use crate::utils::get_image_mime_type;
 
@@ -13,27 +14,47 @@ pub(crate) trait Metadata {
     fn inject_metadata(&mut self, data: Vec<u8>) -> Result<Vec<u8>>;
 }
 
+/// Inject the metadata by the extension of data, other formats are returned directly.
+pub(crate) fn inject_metadata(
+    format: AudioFormat,
+    info: Option<&NcmInfo>,
+    images: &[NcmImage],
+    data: Vec<u8>,
+) -> Result<Vec<u8>> {
+    match format {
+        AudioFormat::Mp3 => Mp3Metadata::new(info, images, &data).inject_metadata(data),
+        AudioFormat::Flac => FlacMetadata::new(info, images, &data).inject_metadata(data),
+        _ => Ok(data),
+    }
+}
+
 pub(crate) struct Mp3Metadata(id3::Tag);
 
 impl Mp3Metadata {
-    pub(crate) fn new(info: &NcmInfo, image: &[u8], data: &[u8]) -> Self {
+    pub(crate) fn new(info: Option<&NcmInfo>, images: &[NcmImage], data: &[u8]) -> Self {
         let cursor = Cursor::new(data.to_vec());
         let mut tag = id3::Tag::read_from2(cursor).unwrap_or_else(|_| id3::Tag::new());
-        let artist = info
-            .artist
-            .iter()
-            .map(|item| item.0.to_owned())
-            .collect::<Vec<String>>()
-            .join("/");
-        tag.set_title(&info.name);
-        tag.set_album(&info.album);
-        tag.set_artist(artist);
-        if !image.is_empty() {
+        if let Some(info) = info {
+            let artist = info
+                .artist
+                .iter()
+                .map(|item| item.0.to_owned())
+                .collect::<Vec<String>>()
+                .join("/");
+            tag.set_title(&info.name);
+            tag.set_album(&info.album);
+            tag.set_artist(artist);
+        }
+        for image in images.iter().filter(|image| !image.data.is_empty()) {
+            let picture_type = match image.kind {
+                NcmImageKind::Cover => id3::frame::PictureType::CoverFront,
+                NcmImageKind::Other => id3::frame::PictureType::Other,
+            };
             tag.add_frame(Picture {
-                mime_type: get_image_mime_type(image).to_owned(),
-                picture_type: id3::frame::PictureType::CoverFront,
+                mime_type: get_image_mime_type(&image.data).to_owned(),
+                picture_type,
                 description: "".to_string(),
-                data: image.to_vec(),
+                data: image.data.clone(),
             });
         }
         Self(tag)
@@ -52,24 +73,30 @@ impl Metadata for Mp3Metadata {
 pub(crate) struct FlacMetadata(metaflac::Tag);
 
 impl FlacMetadata {
-    pub(crate) fn new(info: &NcmInfo, image: &[u8], data: &[u8]) -> Self {
+    pub(crate) fn new(info: Option<&NcmInfo>, images: &[NcmImage], data: &[u8]) -> Self {
         let mut tag = metaflac::Tag::read_from(&mut Cursor::new(&data))
             .unwrap_or_else(|_| metaflac::Tag::new());
-        let mc = tag.vorbis_comments_mut();
-        let artist = info
-            .artist
-            .iter()
-            .cloned()
-            .map(|item| item.0)
-            .collect::<Vec<String>>();
-        mc.set_title(vec![info.name.to_string()]);
-        mc.set_album(vec![info.album.to_string()]);
-        mc.set_artist(artist);
-        if !image.is_empty() {
+        if let Some(info) = info {
+            let mc = tag.vorbis_comments_mut();
+            let artist = info
+                .artist
+                .iter()
+                .cloned()
+                .map(|item| item.0)
+                .collect::<Vec<String>>();
+            mc.set_title(vec![info.name.to_string()]);
+            mc.set_album(vec![info.album.to_string()]);
+            mc.set_artist(artist);
+        }
+        for image in images.iter().filter(|image| !image.data.is_empty()) {
+            let picture_type = match image.kind {
+                NcmImageKind::Cover => metaflac::block::PictureType::CoverFront,
+                NcmImageKind::Other => metaflac::block::PictureType::Other,
+            };
             tag.add_picture(
-                get_image_mime_type(image),
-                metaflac::block::PictureType::CoverFront,
-                image.to_vec(),
+                get_image_mime_type(&image.data),
+                picture_type,
+                image.data.clone(),
             );
         }
         Self(tag)
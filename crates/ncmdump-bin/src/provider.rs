@@ -2,21 +2,18 @@ use std::fs::File;
 use std::path::PathBuf;
 
 use anyhow::Result;
-use ncmdump::utils::FileType;
 
 use crate::errors::Error;
 
 pub(crate) trait DataProvider {
     fn get_name(&self) -> String;
     fn get_path(&self) -> PathBuf;
-    fn get_format(&self) -> FileType;
     fn get_size(&self) -> u64;
 }
 
 pub(crate) struct FileProvider {
     path: PathBuf,
     name: String,
-    format: FileType,
     size: u64,
 }
 
@@ -31,11 +28,6 @@ impl DataProvider for FileProvider {
         self.path.clone()
     }
 
-    #[inline]
-    fn get_format(&self) -> FileType {
-        self.format.clone()
-    }
-
     #[inline]
     fn get_size(&self) -> u64 {
         self.size
@@ -45,8 +37,7 @@ impl DataProvider for FileProvider {
 impl FileProvider {
     pub(crate) fn new(path: PathBuf) -> Result<Self> {
         let path = path.clone();
-        let mut file = File::open(path.clone())?;
-        let format = FileType::parse(&mut file)?;
+        let file = File::open(path.clone())?;
         let size = file.metadata().map_err(|_| Error::Metadata)?.len();
         let name = path
             .file_name()
@@ -54,11 +45,6 @@ impl FileProvider {
             .to_str()
             .ok_or(Error::Path(String::from("Can't convert filename as str")))?
             .to_string();
-        Ok(FileProvider {
-            name,
-            format,
-            path,
-            size,
-        })
+        Ok(FileProvider { name, path, size })
     }
 }
@@ -10,7 +10,7 @@ use ncmdump::QmcDump;
 
 fn main() -> Result<(), Error> {
     let file = File::open("res/test.qmcflac")?;
-    let mut qmc = QmcDump::from_reader(file).expect("Can't create dump");
+    let mut qmc = QmcDump::from_seekable_reader(file).expect("Can't create dump");
     let data = qmc.get_data()?;
 
     let mut target = File::options()
@@ -0,0 +1,2 @@
+ࣣpӣ7Fou3'V<٣󣣣ǳ`ٳS)㣣379S㣣|
+3^PC泣ꓣ*}3eC߱}O#eϳsDc#֓۳# sٳ(1ӣ0RƃJ:J:G"F0"
\ No newline at end of file
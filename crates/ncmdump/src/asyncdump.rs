@@ -0,0 +1,329 @@
+//! The async dumpers for the tokio runtime.
+//!
+//! They share the sans-IO parser and the cipher in [`crate::raw`]
+//! with the blocking dumpers, but read the file by `AsyncRead` and `AsyncSeek`.
+use std::io::SeekFrom;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+#[cfg(feature = "ncmdump")]
+use tokio::io::AsyncSeekExt;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeek, ReadBuf};
+
+#[cfg(feature = "ncmdump")]
+use crate::dump::NcmImage;
+use crate::error::Result;
+#[cfg(feature = "ncmdump")]
+use crate::ncmdump::{NcmSections, NcmSectionsParser};
+#[cfg(feature = "ncmdump")]
+use crate::raw::ncm::{self, NcmHeader, NcmInfo};
+#[cfg(feature = "qmcdump")]
+use crate::raw::qmc;
+
+/// The async ncm file dump wrapper.
+#[cfg(feature = "ncmdump")]
+pub struct AsyncNcmDump<S>
+where
+    S: AsyncRead + Unpin,
+{
+    reader: S,
+    cursor: u64,
+    base: u64,
+    header: NcmHeader,
+    sections: NcmSections,
+}
+
+#[cfg(feature = "ncmdump")]
+impl<S> AsyncNcmDump<S>
+where
+    S: AsyncRead + AsyncSeek + Unpin,
+{
+    /// Read the header by the parser, the reader stops at the start of music data.
+    async fn read_sections(reader: &mut S) -> Result<(NcmHeader, NcmSections)> {
+        let mut parser = NcmSectionsParser::new();
+        while let Some(length) = parser.next_read()? {
+            let mut data = Vec::new();
+            (&mut *reader)
+                .take(length as u64)
+                .read_to_end(&mut data)
+                .await?;
+            parser.fill(&data)?;
+        }
+        parser.finish()
+    }
+
+    /// Create an AsyncNcmDump from a seekable async reader, such as `tokio::fs::File`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use anyhow::Result;
+    /// use ncmdump::AsyncNcmDump;
+    /// use tokio::fs::File;
+    ///
+    /// #[tokio::main(flavor = "current_thread")]
+    /// async fn main() -> Result<()> {
+    ///     let file = File::open("res/test.ncm").await?;
+    ///     let _ = AsyncNcmDump::from_reader(file).await?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn from_reader(mut reader: S) -> Result<Self> {
+        let start = reader.stream_position().await?;
+        let (header, sections) = Self::read_sections(&mut reader).await?;
+
+        // The position of reader is only updated by seeking, such as `tokio::fs::File`
+        let base = start + header.audio_offset as u64;
+        reader.seek(SeekFrom::Start(base)).await?;
+        Ok(Self {
+            reader,
+            cursor: 0,
+            base,
+            header,
+            sections,
+        })
+    }
+
+    /// Get the layout of header, the offsets are from the start of ncm data.
+    pub fn get_header(&self) -> &NcmHeader {
+        &self.header
+    }
+
+    /// Decode the information buffer and just return the information.
+    /// It returns `None` if there is no information in the file.
+    pub async fn get_info(&mut self) -> Result<Option<NcmInfo>> {
+        self.sections.get_info()
+    }
+
+    /// Get the image bytes, if it's exists.
+    pub async fn get_image(&mut self) -> Result<Option<Vec<u8>>> {
+        Ok(self.sections.get_image().map(<[u8]>::to_vec))
+    }
+
+    /// Get every image in the cover frame, the cover is always the first one.
+    pub async fn get_images(&mut self) -> Result<Vec<NcmImage>> {
+        Ok(self.sections.get_images())
+    }
+
+    /// Get the music data from the rest of reader.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use anyhow::Result;
+    /// use ncmdump::AsyncNcmDump;
+    /// use tokio::fs::File;
+    ///
+    /// #[tokio::main(flavor = "current_thread")]
+    /// async fn main() -> Result<()> {
+    ///     let file = File::open("res/test.ncm").await?;
+    ///     let mut ncm = AsyncNcmDump::from_reader(file).await?;
+    ///     let info = ncm.get_info().await?;
+    ///     let music = ncm.get_data().await?;
+    ///     assert!(music.starts_with(b"fLaC"));
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn get_data(&mut self) -> Result<Vec<u8>> {
+        let mut data = Vec::new();
+        self.read_to_end(&mut data).await?;
+        Ok(data)
+    }
+}
+
+#[cfg(feature = "ncmdump")]
+impl<R> AsyncRead for AsyncNcmDump<R>
+where
+    R: AsyncRead + Unpin,
+{
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        let this = self.get_mut();
+        let filled = buf.filled().len();
+        ready!(Pin::new(&mut this.reader).poll_read(cx, buf))?;
+        let data = &mut buf.filled_mut()[filled..];
+        ncm::encrypt(&this.header.key_box, this.cursor, data);
+        this.cursor += data.len() as u64;
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(feature = "ncmdump")]
+impl<R> AsyncSeek for AsyncNcmDump<R>
+where
+    R: AsyncRead + AsyncSeek + Unpin,
+{
+    fn start_seek(self: Pin<&mut Self>, position: SeekFrom) -> std::io::Result<()> {
+        let this = self.get_mut();
+        let position = match position {
+            SeekFrom::Start(p) => SeekFrom::Start(p.checked_add(this.base).ok_or_else(|| {
+                std::io::Error::new(std::io::ErrorKind::InvalidInput, "invalid seek")
+            })?),
+            _ => position,
+        };
+        Pin::new(&mut this.reader).start_seek(position)
+    }
+
+    fn poll_complete(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<u64>> {
+        let this = self.get_mut();
+        let position = ready!(Pin::new(&mut this.reader).poll_complete(cx))?;
+        this.cursor = position
+            .checked_sub(this.base)
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidInput, "invalid seek"))?;
+        Poll::Ready(Ok(this.cursor))
+    }
+}
+
+/// The async qmc file dump wrapper.
+#[cfg(feature = "qmcdump")]
+pub struct AsyncQmcDump<S>
+where
+    S: AsyncRead + Unpin,
+{
+    reader: S,
+    parser: qmc::Parser,
+}
+
+#[cfg(feature = "qmcdump")]
+impl<S> AsyncQmcDump<S>
+where
+    S: AsyncRead + Unpin,
+{
+    /// Create AsyncQmcDump from async reader, nothing is read until the music data is read.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use anyhow::Result;
+    /// use ncmdump::AsyncQmcDump;
+    /// use tokio::fs::File;
+    ///
+    /// #[tokio::main(flavor = "current_thread")]
+    /// async fn main() -> Result<()> {
+    ///     let file = File::open("res/test.qmcflac").await?;
+    ///     let mut qmc = AsyncQmcDump::from_reader(file)?;
+    ///     let music = qmc.get_data().await?;
+    ///     assert!(music.starts_with(b"fLaC"));
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn from_reader(reader: S) -> Result<Self> {
+        Ok(Self {
+            reader,
+            parser: qmc::Parser::new(),
+        })
+    }
+
+    /// Get the music data from the rest of reader.
+    pub async fn get_data(&mut self) -> Result<Vec<u8>> {
+        let mut data = Vec::new();
+        self.read_to_end(&mut data).await?;
+        Ok(data)
+    }
+}
+
+#[cfg(feature = "qmcdump")]
+impl<R> AsyncRead for AsyncQmcDump<R>
+where
+    R: AsyncRead + Unpin,
+{
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        let this = self.get_mut();
+        let filled = buf.filled().len();
+        ready!(Pin::new(&mut this.reader).poll_read(cx, buf))?;
+        let size = this.parser.decrypt(&mut buf.filled_mut()[filled..]);
+        buf.set_filled(filled + size);
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(feature = "qmcdump")]
+impl<R> AsyncSeek for AsyncQmcDump<R>
+where
+    R: AsyncRead + AsyncSeek + Unpin,
+{
+    fn start_seek(self: Pin<&mut Self>, position: SeekFrom) -> std::io::Result<()> {
+        Pin::new(&mut self.get_mut().reader).start_seek(position)
+    }
+
+    fn poll_complete(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<u64>> {
+        let this = self.get_mut();
+        let position = ready!(Pin::new(&mut this.reader).poll_complete(cx))?;
+        this.parser.set_position(position);
+        Poll::Ready(Ok(position))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "ncmdump")]
+    use std::io::Cursor;
+
+    use anyhow::Result;
+    use tokio::fs::File;
+    use tokio::io::AsyncSeekExt;
+
+    use super::*;
+    #[cfg(feature = "ncmdump")]
+    use crate::error::Errors;
+
+    #[cfg(feature = "ncmdump")]
+    #[tokio::test]
+    async fn test_async_ncmdump_ok() -> Result<()> {
+        let mut expect = crate::NcmDump::from_reader(std::fs::File::open("res/test.ncm")?)?;
+        let mut ncm = AsyncNcmDump::from_reader(File::open("res/test.ncm").await?).await?;
+
+        // The position of music data is kept after reading the information
+        let mut buf = [0; 4];
+        ncm.read_exact(&mut buf).await?;
+        assert_eq!(ncm.get_header(), expect.get_header());
+        assert_eq!(ncm.get_info().await?, expect.get_info()?);
+        assert_eq!(ncm.get_images().await?, expect.get_images()?);
+
+        let mut data = buf.to_vec();
+        data.extend(ncm.get_data().await?);
+        assert_eq!(data, expect.get_data()?);
+        Ok(())
+    }
+
+    #[cfg(feature = "ncmdump")]
+    #[tokio::test]
+    async fn test_async_ncmdump_seek_ok() -> Result<()> {
+        let mut ncm = AsyncNcmDump::from_reader(File::open("res/test.ncm").await?).await?;
+        let mut buf = [0; 4];
+        assert_eq!(ncm.seek(SeekFrom::Start(1)).await?, 1);
+        ncm.read_exact(&mut buf).await?;
+        assert_eq!(buf, [0x4c, 0x61, 0x43, 0x00]);
+        assert_eq!(ncm.seek(SeekFrom::End(0)).await?, 61440);
+        assert!(ncm.seek(SeekFrom::Start(u64::MAX)).await.is_err());
+        Ok(())
+    }
+
+    #[cfg(feature = "ncmdump")]
+    #[tokio::test]
+    async fn test_async_ncmdump_err() {
+        let result = AsyncNcmDump::from_reader(Cursor::new([0; 16])).await;
+        assert!(matches!(result, Err(Errors::InvalidFileType)));
+    }
+
+    #[cfg(feature = "qmcdump")]
+    #[tokio::test]
+    async fn test_async_qmcdump_ok() -> Result<()> {
+        let mut expect = crate::QmcDump::from_reader(std::fs::File::open("res/test.qmcflac")?)?;
+        let mut qmc = AsyncQmcDump::from_reader(File::open("res/test.qmcflac").await?)?;
+        assert_eq!(qmc.get_data().await?, expect.get_data()?);
+
+        let mut buf = [0; 4];
+        qmc.seek(SeekFrom::Start(1)).await?;
+        qmc.read_exact(&mut buf).await?;
+        assert_eq!(buf, [0x4c, 0x61, 0x43, 0x00]);
+        Ok(())
+    }
+}
@@ -0,0 +1,215 @@
+//! The format of audio, which is sniffed from the decrypted data.
+//!
+//! The container is told by the magic header, and the mp3 file without id3 tag
+//! is told by the sync word of the first frame.
+//!
+//! # Example
+//!
+//! ```rust
+//! use ncmdump::audio::AudioFormat;
+//!
+//! let format = AudioFormat::sniff(b"fLaC\x00\x00\x00\x22");
+//! assert_eq!(format, Some(AudioFormat::Flac));
+//! assert_eq!(format.map(|format| format.extension()), Some("flac"));
+//! assert_eq!(format.map(|format| format.mime_type()), Some("audio/flac"));
+//! ```
+
+/// The length of data which is enough to sniff the format.
+pub const SNIFF_SIZE: usize = 16;
+
+/// The format of audio.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum AudioFormat {
+    /// The flac file, starts with `fLaC`.
+    Flac,
+    /// The mp3 file, starts with `ID3` or the sync word of frame.
+    Mp3,
+    /// The ogg file, starts with `OggS`.
+    Ogg,
+    /// The m4a file, which has the `ftyp` box. It's also used for the alac audio.
+    M4a,
+    /// The wav file, which is the `RIFF` file with `WAVE` type.
+    Wav,
+    /// The monkey's audio file, starts with `MAC `.
+    Ape,
+    /// The wma file, which starts with the guid of asf header.
+    Wma,
+    /// The dsf file, starts with `DSD `.
+    Dsf,
+}
+
+/// The guid of asf header object.
+const ASF_HEADER: [u8; 16] = [
+    0x30, 0x26, 0xB2, 0x75, 0x8E, 0x66, 0xCF, 0x11, 0xA6, 0xD9, 0x00, 0xAA, 0x00, 0x62, 0xCE, 0x6C,
+];
+
+impl AudioFormat {
+    /// Sniff the format of audio from the head of decrypted data.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use ncmdump::audio::AudioFormat;
+    /// #
+    /// assert_eq!(AudioFormat::sniff(b"ID3\x04"), Some(AudioFormat::Mp3));
+    /// assert_eq!(AudioFormat::sniff(b"\x00\x00\x00\x00"), None);
+    /// ```
+    pub fn sniff(buffer: &[u8]) -> Option<Self> {
+        match buffer {
+            [0x66, 0x4C, 0x61, 0x43, ..] => Some(AudioFormat::Flac),
+            [0x49, 0x44, 0x33, ..] => Some(AudioFormat::Mp3),
+            [0x4F, 0x67, 0x67, 0x53, ..] => Some(AudioFormat::Ogg),
+            [_, _, _, _, 0x66, 0x74, 0x79, 0x70, ..] => Some(AudioFormat::M4a),
+            [0x52, 0x49, 0x46, 0x46, _, _, _, _, 0x57, 0x41, 0x56, 0x45, ..] => {
+                Some(AudioFormat::Wav)
+            }
+            [0x4D, 0x41, 0x43, 0x20, ..] => Some(AudioFormat::Ape),
+            [0x44, 0x53, 0x44, 0x20, ..] => Some(AudioFormat::Dsf),
+            buffer if buffer.starts_with(&ASF_HEADER) => Some(AudioFormat::Wma),
+            [0xFF, b1, b2, ..] if Self::is_mp3_frame(*b1, *b2) => Some(AudioFormat::Mp3),
+            _ => None,
+        }
+    }
+
+    /// Check the header of mpeg audio frame after the sync byte.
+    /// The layer, bitrate and sample rate should not be reserved or invalid.
+    fn is_mp3_frame(b1: u8, b2: u8) -> bool {
+        let sync = b1 & 0xE0 == 0xE0;
+        let version = (b1 >> 3) & 0x03;
+        let layer = (b1 >> 1) & 0x03;
+        let bitrate = b2 >> 4;
+        let sample_rate = (b2 >> 2) & 0x03;
+        sync && version != 0x01 && layer != 0x00 && bitrate != 0x0F && sample_rate != 0x03
+    }
+
+    /// Get the format by the extension of file.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use ncmdump::audio::AudioFormat;
+    /// #
+    /// assert_eq!(AudioFormat::from_extension("FLAC"), Some(AudioFormat::Flac));
+    /// ```
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension.to_ascii_lowercase().as_str() {
+            "flac" => Some(AudioFormat::Flac),
+            "mp3" => Some(AudioFormat::Mp3),
+            "ogg" => Some(AudioFormat::Ogg),
+            "m4a" => Some(AudioFormat::M4a),
+            "wav" => Some(AudioFormat::Wav),
+            "ape" => Some(AudioFormat::Ape),
+            "wma" => Some(AudioFormat::Wma),
+            "dsf" => Some(AudioFormat::Dsf),
+            _ => None,
+        }
+    }
+
+    /// Get the extension of file, without the dot.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            AudioFormat::Flac => "flac",
+            AudioFormat::Mp3 => "mp3",
+            AudioFormat::Ogg => "ogg",
+            AudioFormat::M4a => "m4a",
+            AudioFormat::Wav => "wav",
+            AudioFormat::Ape => "ape",
+            AudioFormat::Wma => "wma",
+            AudioFormat::Dsf => "dsf",
+        }
+    }
+
+    /// Get the mime type of audio.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use ncmdump::audio::AudioFormat;
+    /// #
+    /// assert_eq!(AudioFormat::Mp3.mime_type(), "audio/mpeg");
+    /// ```
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            AudioFormat::Flac => "audio/flac",
+            AudioFormat::Mp3 => "audio/mpeg",
+            AudioFormat::Ogg => "audio/ogg",
+            AudioFormat::M4a => "audio/mp4",
+            AudioFormat::Wav => "audio/wav",
+            AudioFormat::Ape => "audio/x-ape",
+            AudioFormat::Wma => "audio/x-ms-wma",
+            AudioFormat::Dsf => "audio/x-dsf",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sniff_ok() {
+        assert_eq!(AudioFormat::sniff(b"fLaC"), Some(AudioFormat::Flac));
+        assert_eq!(AudioFormat::sniff(b"ID3"), Some(AudioFormat::Mp3));
+        assert_eq!(AudioFormat::sniff(b"OggS"), Some(AudioFormat::Ogg));
+        assert_eq!(
+            AudioFormat::sniff(b"\x00\x00\x00\x20ftypM4A "),
+            Some(AudioFormat::M4a)
+        );
+        assert_eq!(
+            AudioFormat::sniff(b"RIFF\x24\x00\x00\x00WAVEfmt "),
+            Some(AudioFormat::Wav)
+        );
+        assert_eq!(
+            AudioFormat::sniff(b"\x00\x00\x00\x20ftypM4A \x00\x00\x00\x00"),
+            Some(AudioFormat::M4a)
+        );
+        assert_eq!(AudioFormat::sniff(b"MAC \x96\x0f"), Some(AudioFormat::Ape));
+        assert_eq!(AudioFormat::sniff(&ASF_HEADER), Some(AudioFormat::Wma));
+        assert_eq!(AudioFormat::sniff(b"DSD \x1c\x00"), Some(AudioFormat::Dsf));
+    }
+
+    #[test]
+    fn test_sniff_mp3_frame_ok() {
+        // MPEG-1 Layer III, 128kbps, 44100Hz
+        assert_eq!(
+            AudioFormat::sniff(b"\xFF\xFB\x90\x64"),
+            Some(AudioFormat::Mp3)
+        );
+        // MPEG-2 Layer III, 64kbps, 22050Hz
+        assert_eq!(
+            AudioFormat::sniff(b"\xFF\xF3\x80\xC4"),
+            Some(AudioFormat::Mp3)
+        );
+        // The aac adts header has the reserved layer
+        assert_eq!(AudioFormat::sniff(b"\xFF\xF1\x50\x80"), None);
+        // The bitrate is invalid
+        assert_eq!(AudioFormat::sniff(b"\xFF\xFB\xF0\x64"), None);
+    }
+
+    #[test]
+    fn test_sniff_none() {
+        assert_eq!(AudioFormat::sniff(b""), None);
+        assert_eq!(AudioFormat::sniff(b"fLa"), None);
+        assert_eq!(AudioFormat::sniff(b"RIFF\x24\x00\x00\x00AVI "), None);
+    }
+
+    #[test]
+    fn test_extension_ok() {
+        for format in [
+            AudioFormat::Flac,
+            AudioFormat::Mp3,
+            AudioFormat::Ogg,
+            AudioFormat::M4a,
+            AudioFormat::Wav,
+            AudioFormat::Ape,
+            AudioFormat::Wma,
+            AudioFormat::Dsf,
+        ] {
+            assert_eq!(
+                AudioFormat::from_extension(format.extension()),
+                Some(format)
+            );
+        }
+        assert_eq!(AudioFormat::from_extension("ncm"), None);
+    }
+}
@@ -0,0 +1,146 @@
+use std::io::{Read, Seek, SeekFrom};
+
+use crate::audio::{AudioFormat, SNIFF_SIZE};
+use crate::error::Result;
+#[cfg(feature = "ncmdump")]
+use crate::NcmInfo;
+
+/// The type of image in the file.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum NcmImageKind {
+    /// The cover of album, it's the first image of cover frame.
+    Cover,
+    /// The other image after the cover, such as the image of album or artist in newer files.
+    Other,
+}
+
+/// The image in the file, such as the cover frame of ncm file.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct NcmImage {
+    /// The type of image
+    pub kind: NcmImageKind,
+    /// The data of image
+    pub data: Vec<u8>,
+}
+
+/// The common interface of every dumper.
+///
+/// The decrypted audio is read by the `Read` trait,
+/// and the information or cover is returned if the file contains it.
+///
+/// # Example
+///
+/// ```rust
+/// use std::fs::File;
+/// use std::io::Read;
+///
+/// use anyhow::Result;
+/// use ncmdump::audio::AudioFormat;
+/// use ncmdump::{Dump, NcmDump, QmcDump};
+///
+/// fn main() -> Result<()> {
+///     let dumps: Vec<Box<dyn Dump>> = vec![
+///         Box::new(NcmDump::from_reader(File::open("res/test.ncm")?)?),
+///         Box::new(QmcDump::from_seekable_reader(File::open("res/test.qmcflac")?)?),
+///     ];
+///     for mut dump in dumps {
+///         assert_eq!(dump.get_audio_format()?, Some(AudioFormat::Flac));
+///         let mut data = Vec::new();
+///         dump.read_to_end(&mut data)?;
+///     }
+///     Ok(())
+/// }
+/// ```
+pub trait Dump: Read + Seek {
+    /// Get the information of music, if the file contains it.
+    #[cfg(feature = "ncmdump")]
+    fn get_info(&mut self) -> Result<Option<NcmInfo>> {
+        Ok(None)
+    }
+
+    /// Get the cover of music, if the file contains it.
+    fn get_cover(&mut self) -> Result<Option<Vec<u8>>> {
+        Ok(None)
+    }
+
+    /// Get every image in the file, the cover is always the first one.
+    fn get_images(&mut self) -> Result<Vec<NcmImage>> {
+        let images = self
+            .get_cover()?
+            .map(|data| NcmImage {
+                kind: NcmImageKind::Cover,
+                data,
+            })
+            .into_iter()
+            .collect();
+        Ok(images)
+    }
+
+    /// Get the format of audio, it's sniffed from the head of decrypted data by default.
+    /// The position of audio stream is kept.
+    fn get_audio_format(&mut self) -> Result<Option<AudioFormat>> {
+        let position = self.stream_position()?;
+        self.seek(SeekFrom::Start(0))?;
+        let mut buffer = Vec::with_capacity(SNIFF_SIZE);
+        Read::take(&mut *self, SNIFF_SIZE as u64).read_to_end(&mut buffer)?;
+        self.seek(SeekFrom::Start(position))?;
+        Ok(AudioFormat::sniff(&buffer))
+    }
+}
+
+#[cfg(all(
+    test,
+    any(
+        feature = "qmcdump",
+        feature = "xmdump",
+        all(feature = "ncmdump", feature = "kwmdump")
+    )
+))]
+mod tests {
+    use std::fs::File;
+
+    use anyhow::Result;
+
+    use super::*;
+
+    #[cfg(feature = "qmcdump")]
+    #[test]
+    fn test_get_audio_format_ok() -> Result<()> {
+        let mut dump: Box<dyn Dump> = Box::new(crate::QmcDump::from_reader(File::open(
+            "res/test.qmcflac",
+        )?)?);
+        let mut buf = [0; 4];
+        dump.seek(SeekFrom::Start(4))?;
+        assert_eq!(dump.get_audio_format()?, Some(AudioFormat::Flac));
+        dump.read_exact(&mut buf)?;
+        assert_eq!(buf, [0x00, 0x00, 0x00, 0x22]);
+        Ok(())
+    }
+
+    #[cfg(all(feature = "ncmdump", feature = "kwmdump"))]
+    #[test]
+    fn test_get_images_ok() -> Result<()> {
+        let mut dump: Box<dyn Dump> =
+            Box::new(crate::NcmDump::from_reader(File::open("res/test.ncm")?)?);
+        assert!(dump.get_info()?.is_some());
+        assert_eq!(dump.get_cover()?.map(|cover| cover.len()), Some(39009));
+        let images = dump.get_images()?;
+        assert_eq!(images.len(), 1);
+        assert_eq!(images[0].kind, NcmImageKind::Cover);
+
+        let mut dump: Box<dyn Dump> =
+            Box::new(crate::KwmDump::from_reader(File::open("res/test.kwm")?)?);
+        assert!(dump.get_info()?.is_none());
+        assert!(dump.get_images()?.is_empty());
+        Ok(())
+    }
+
+    #[cfg(feature = "xmdump")]
+    #[test]
+    fn test_get_audio_format_header_ok() -> Result<()> {
+        let mut dump: Box<dyn Dump> =
+            Box::new(crate::XmDump::from_reader(File::open("res/test.xm")?)?);
+        assert_eq!(dump.get_audio_format()?, Some(AudioFormat::Flac));
+        Ok(())
+    }
+}
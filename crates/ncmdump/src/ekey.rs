@@ -0,0 +1,216 @@
+//! The decryption of ekey, which is used by the qmc v2 file.
+//!
+//! The ekey is found at the end of file, or in the database of QQ Music client.
+//! It should be decrypted before using as the key of [`QmcV2Dump`](crate::QmcV2Dump).
+//!
+//! # Example
+//!
+//! ```rust
+//! use ncmdump::ekey;
+//!
+//! let key = ekey::decrypt(b"VGVzdEtleUbj/fFgGTIpxEXpSJ9uJiJLj5ZZm7TgLk+1bMB+Sqxb9Q==").unwrap();
+//! assert_eq!(key, b"TestKeyForQmcV2Dump0123456789");
+//! ```
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+
+use crate::error::{Errors, Result};
+
+const SIMPLE_KEY_SEED: u8 = 106;
+const SIMPLE_KEY_LENGTH: usize = 8;
+
+const ENC_V2_PREFIX: &[u8] = b"QQMusic EncV2,Key:";
+const ENC_V2_KEY_1: [u8; 16] = [
+    0x33, 0x38, 0x36, 0x5A, 0x4A, 0x59, 0x21, 0x40, 0x23, 0x2A, 0x24, 0x25, 0x5E, 0x26, 0x29, 0x28,
+];
+const ENC_V2_KEY_2: [u8; 16] = [
+    0x2A, 0x2A, 0x23, 0x21, 0x28, 0x23, 0x24, 0x25, 0x26, 0x5E, 0x61, 0x31, 0x63, 0x5A, 0x2C, 0x54,
+];
+
+const TEA_ROUNDS: u32 = 16;
+const TEA_DELTA: u32 = 0x9E37_79B9;
+const TEA_SALT_LENGTH: usize = 2;
+const TEA_ZERO_LENGTH: usize = 7;
+
+/// Build the simple key which is mixed with the head of ekey.
+/// The ekey uses the seed `106` and the length `8`.
+pub fn make_simple_key(seed: u8, length: usize) -> Vec<u8> {
+    (0..length)
+        .map(|i| {
+            let value = seed as f64 + i as f64 * 0.1;
+            (value.tan().abs() * 100.0) as u8
+        })
+        .collect()
+}
+
+/// Decrypt a single block with the 16 rounds TEA.
+fn tea_decrypt_block(block: u64, key: &[u32; 4]) -> u64 {
+    let round = |value: u32, sum: u32, key1: u32, key2: u32| {
+        let left = (value << 4).wrapping_add(key1);
+        let right = (value >> 5).wrapping_add(key2);
+        left ^ sum.wrapping_add(value) ^ right
+    };
+
+    let mut y = (block >> 32) as u32;
+    let mut z = block as u32;
+    let mut sum = TEA_DELTA.wrapping_mul(TEA_ROUNDS);
+    for _ in 0..TEA_ROUNDS {
+        z = z.wrapping_sub(round(y, sum, key[2], key[3]));
+        y = y.wrapping_sub(round(z, sum, key[0], key[1]));
+        sum = sum.wrapping_sub(TEA_DELTA);
+    }
+    (y as u64) << 32 | z as u64
+}
+
+/// Decrypt the data with the tencent flavored TEA in CBC mode.
+/// The key is read as four big-endian `u32`, and the salt and padding are removed.
+pub fn tc_tea_decrypt(data: &[u8], key: &[u8; 16]) -> Result<Vec<u8>> {
+    if data.len() < 1 + TEA_SALT_LENGTH + TEA_ZERO_LENGTH || !data.len().is_multiple_of(8) {
+        return Err(Errors::DecryptError);
+    }
+
+    let mut tea_key = [0u32; 4];
+    for (k, chunk) in tea_key.iter_mut().zip(key.chunks_exact(4)) {
+        *k = u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+    }
+
+    let mut plain = Vec::with_capacity(data.len());
+    let (mut iv1, mut iv2) = (0u64, 0u64);
+    for chunk in data.chunks_exact(8) {
+        let block = u64::from_be_bytes(chunk.try_into().map_err(|_| Errors::DecryptError)?);
+        let next_iv2 = tea_decrypt_block(block ^ iv2, &tea_key);
+        plain.extend_from_slice(&(next_iv2 ^ iv1).to_be_bytes());
+        (iv1, iv2) = (block, next_iv2);
+    }
+
+    let start = 1 + (plain[0] & 0x7) as usize + TEA_SALT_LENGTH;
+    let end = plain.len() - TEA_ZERO_LENGTH;
+    if start > end || plain[end..].iter().any(|&byte| byte != 0) {
+        return Err(Errors::DecryptError);
+    }
+    Ok(plain[start..end].to_vec())
+}
+
+fn trim_end_zero(data: &[u8]) -> &[u8] {
+    match data.iter().rposition(|&byte| byte != 0) {
+        Some(index) => &data[..=index],
+        None => &[],
+    }
+}
+
+/// Decrypt the ekey which is decoded from base64.
+fn decrypt_v1(ekey: &[u8]) -> Result<Vec<u8>> {
+    if ekey.len() < 8 {
+        return Err(Errors::InvalidKeyLength);
+    }
+
+    let simple_key = make_simple_key(SIMPLE_KEY_SEED, SIMPLE_KEY_LENGTH);
+    let mut tea_key = [0u8; 16];
+    for i in 0..8 {
+        tea_key[i << 1] = simple_key[i];
+        tea_key[(i << 1) + 1] = ekey[i];
+    }
+
+    let mut key = ekey[..8].to_vec();
+    key.extend(tc_tea_decrypt(&ekey[8..], &tea_key)?);
+    Ok(key)
+}
+
+/// Unwrap the ekey after the prefix `QQMusic EncV2,Key:`.
+fn decrypt_v2(ekey: &[u8]) -> Result<Vec<u8>> {
+    let ekey = tc_tea_decrypt(ekey, &ENC_V2_KEY_1)?;
+    let ekey = tc_tea_decrypt(&ekey, &ENC_V2_KEY_2)?;
+    STANDARD
+        .decode(trim_end_zero(&ekey))
+        .map_err(|_| Errors::Decode)
+}
+
+/// Decrypt the base64 encoded ekey, and return the real key of file.
+/// The ekey wrapped by `QQMusic EncV2,Key:` is also supported.
+///
+/// # Example
+///
+/// ```rust
+/// # use ncmdump::ekey;
+/// #
+/// let key = ekey::decrypt(b"VGVzdEtleUbj/fFgGTIpxEXpSJ9uJiJLj5ZZm7TgLk+1bMB+Sqxb9Q==").unwrap();
+/// assert_eq!(key, b"TestKeyForQmcV2Dump0123456789");
+/// ```
+pub fn decrypt(ekey: &[u8]) -> Result<Vec<u8>> {
+    let ekey = trim_end_zero(ekey);
+    if ekey.is_empty() {
+        return Err(Errors::InvalidKeyLength);
+    }
+    let ekey = STANDARD.decode(ekey).map_err(|_| Errors::Decode)?;
+    match ekey.strip_prefix(ENC_V2_PREFIX) {
+        Some(ekey) => decrypt_v1(&decrypt_v2(ekey)?),
+        None => decrypt_v1(&ekey),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_make_simple_key_ok() {
+        let key = make_simple_key(SIMPLE_KEY_SEED, SIMPLE_KEY_LENGTH);
+        assert_eq!(key, [0x69, 0x56, 0x46, 0x38, 0x2B, 0x20, 0x15, 0x0B]);
+    }
+
+    #[test]
+    fn test_tc_tea_decrypt_ok() -> Result<()> {
+        let data = [
+            0x91, 0x09, 0x51, 0x62, 0xE3, 0xF5, 0xB6, 0xDC, 0x6B, 0x41, 0x4B, 0x50, 0xD1, 0xA5,
+            0xB8, 0x4E, 0xC5, 0x0D, 0x0C, 0x1B, 0x11, 0x96, 0xFD, 0x3C,
+        ];
+        let result = tc_tea_decrypt(&data, b"12345678ABCDEFGH")?;
+        assert_eq!(result, [0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_tc_tea_decrypt_err() {
+        let data = [
+            0x91, 0x09, 0x51, 0x62, 0xE3, 0xF5, 0xB6, 0xDC, 0x6B, 0x41, 0x4B, 0x50, 0xD1, 0xA5,
+            0xB8, 0x4E, 0xC5, 0x0D, 0x0C, 0x1B, 0x11, 0x96, 0xFD, 0xC3,
+        ];
+        let result = tc_tea_decrypt(&data, b"12345678ABCDEFGH");
+        assert!(matches!(result, Err(Errors::DecryptError)));
+
+        let result = tc_tea_decrypt(&data[..20], b"12345678ABCDEFGH");
+        assert!(matches!(result, Err(Errors::DecryptError)));
+
+        // The padding length in the first byte runs past the zero tail.
+        let data = [
+            0xEC, 0xB8, 0x55, 0x2F, 0x6C, 0xE2, 0x17, 0x68, 0xE4, 0x77, 0x71, 0x71, 0xEC, 0x7A,
+            0xDB, 0x5C,
+        ];
+        let result = tc_tea_decrypt(&data, b"12345678ABCDEFGH");
+        assert!(matches!(result, Err(Errors::DecryptError)));
+    }
+
+    #[test]
+    fn test_decrypt_err() {
+        assert!(matches!(decrypt(b""), Err(Errors::InvalidKeyLength)));
+        assert!(matches!(decrypt(b"AAAA"), Err(Errors::InvalidKeyLength)));
+        assert!(matches!(decrypt(b"!@#$"), Err(Errors::Decode)));
+    }
+
+    #[test]
+    fn test_decrypt_v1_ok() -> Result<()> {
+        let key = decrypt(b"VGVzdEtleUbj/fFgGTIpxEXpSJ9uJiJLj5ZZm7TgLk+1bMB+Sqxb9Q==")?;
+        assert_eq!(key, b"TestKeyForQmcV2Dump0123456789");
+        Ok(())
+    }
+
+    #[test]
+    fn test_decrypt_v2_ok() -> Result<()> {
+        let key = decrypt(
+            b"UVFNdXNpYyBFbmNWMixLZXk6d/S8i//PVeTDY07VqgSqtL8uApEGEAyM+AE/ZHunrn3AErshdNtT3A3C\
+n1JPmPs+9xd7cpHN4ztUiPlWPsAypPkAxRZitlyc37c36UDv3GhVUiKf5Qm2qg==",
+        )?;
+        assert_eq!(key, b"TestKeyForQmcV2Dump0123456789");
+        Ok(())
+    }
+}
@@ -1,8 +1,9 @@
-use std::io;
+use alloc::string::String;
 
 use thiserror::Error;
 
-pub(crate) type Result<T> = std::result::Result<T, Errors>;
+#[cfg(any(feature = "std", feature = "ncmdump", feature = "qmcdump"))]
+pub(crate) type Result<T> = core::result::Result<T, Errors>;
 
 /// The error type for ncmdump.
 #[derive(Debug, Error)]
@@ -27,6 +28,18 @@ pub enum Errors {
     #[error("Can't decode information")]
     InfoDecodeError,
 
+    /// The trailer of file is invalid
+    #[error("Invalid trailer")]
+    InvalidTrailer,
+
+    /// The key of file can't be found
+    #[error("Key not found")]
+    KeyNotFound,
+
+    /// The version of file is not supported
+    #[error("Unsupported version")]
+    UnsupportedVersion,
+
     /// Can't decrypt data
     #[error("Can't decrypt")]
     DecryptError,
@@ -44,8 +57,9 @@ pub enum Errors {
     IO(String),
 }
 
-impl From<io::Error> for Errors {
-    fn from(value: io::Error) -> Self {
+#[cfg(feature = "std")]
+impl From<std::io::Error> for Errors {
+    fn from(value: std::io::Error) -> Self {
         Self::IO(value.to_string())
     }
 }
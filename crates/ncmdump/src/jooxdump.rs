@@ -0,0 +1,270 @@
+use std::io::{Read, Seek, SeekFrom, Write};
+
+use aes::Aes128;
+use cipher::block_padding::Pkcs7;
+use cipher::{BlockDecrypt, KeyInit};
+use pbkdf2::pbkdf2_hmac;
+use sha1::Sha1;
+
+use crate::dump::Dump;
+use crate::error::{Errors, Result};
+
+const BUFFER_SIZE: usize = 8192;
+const HEADER_SIZE: usize = 12;
+const MAGIC: &[u8; 4] = b"E!04";
+
+/// Every encrypted block is decrypted as a plain block and the padding.
+const ENCRYPTED_BLOCK_SIZE: u64 = 0x100010;
+const PLAIN_BLOCK_SIZE: u64 = 0x100000;
+
+const SALT: [u8; 16] = [
+    0xA4, 0x0B, 0xC8, 0x34, 0xD6, 0x95, 0xF3, 0x13, 0x23, 0x23, 0x43, 0x23, 0x54, 0x63, 0x83, 0xF3,
+];
+const ITERATIONS: u32 = 1000;
+
+/// The joox v4 file dump wrapper, such as `.ofl_en` file.
+pub struct JooxDump<S>
+where
+    S: Read,
+{
+    reader: S,
+    cursor: u64,
+    cipher: Aes128,
+    block: Vec<u8>,
+    block_start: u64,
+}
+
+impl<S> JooxDump<S>
+where
+    S: Read,
+{
+    /// Derive the aes key from the uuid of device.
+    fn derive_key(uuid: &str) -> [u8; 16] {
+        let mut key = [0; 16];
+        pbkdf2_hmac::<Sha1>(uuid.as_bytes(), &SALT, ITERATIONS, &mut key);
+        key
+    }
+
+    /// Read and decrypt the next block, the block is empty at the end of reader.
+    fn next_block(&mut self) -> std::io::Result<()> {
+        let mut buffer = Vec::new();
+        self.reader
+            .by_ref()
+            .take(ENCRYPTED_BLOCK_SIZE)
+            .read_to_end(&mut buffer)?;
+        let block = match buffer.is_empty() {
+            true => Vec::new(),
+            false => self
+                .cipher
+                .decrypt_padded_vec::<Pkcs7>(&buffer)
+                .map_err(|_| {
+                    std::io::Error::new(std::io::ErrorKind::InvalidData, Errors::DecryptError)
+                })?,
+        };
+        self.block_start += self.block.len() as u64;
+        self.block = block;
+        Ok(())
+    }
+
+    /// Create JooxDump from reader and the uuid of device which downloaded the file.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use std::fs::File;
+    /// #
+    /// # use ncmdump::JooxDump;
+    /// #
+    /// let file = File::open("res/test.ofl_en").expect("Can't open file");
+    /// let _ = JooxDump::from_reader(file, "0123456789abcdef0123456789abcdef").unwrap();
+    /// ```
+    pub fn from_reader(mut reader: S, uuid: &str) -> Result<Self> {
+        let mut header = [0; HEADER_SIZE];
+        reader
+            .read_exact(&mut header)
+            .map_err(|_| Errors::InvalidFileType)?;
+        if !header.starts_with(MAGIC) {
+            return Err(Errors::InvalidFileType);
+        }
+
+        let key = Self::derive_key(uuid);
+        Ok(Self {
+            reader,
+            cursor: 0,
+            cipher: Aes128::new(&key.into()),
+            block: Vec::new(),
+            block_start: 0,
+        })
+    }
+
+    /// Get the music data from jooxdump.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use std::fs::File;
+    /// use std::io::Write;
+    ///
+    /// use anyhow::Result;
+    /// use ncmdump::JooxDump;
+    ///
+    /// fn main() -> Result<()> {
+    ///     let file = File::open("res/test.ofl_en")?;
+    ///     let mut joox = JooxDump::from_reader(file, "0123456789abcdef0123456789abcdef")?;
+    ///     let music = joox.get_data()?;
+    ///
+    ///     let mut target = File::options()
+    ///         .create(true)
+    ///         .write(true)
+    ///         .open("res/test.flac")?;
+    ///     target.write_all(&music)?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn get_data(&mut self) -> Result<Vec<u8>> {
+        let mut data = Vec::new();
+        let mut buffer = [0; BUFFER_SIZE];
+        loop {
+            let size = self.read(&mut buffer)?;
+            if size == 0 {
+                break;
+            }
+            data.write_all(&buffer[..size])?;
+        }
+        Ok(data)
+    }
+}
+
+impl<R> Read for JooxDump<R>
+where
+    R: Read,
+{
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.cursor >= self.block_start + self.block.len() as u64 {
+            self.next_block()?;
+        }
+        let offset = (self.cursor - self.block_start) as usize;
+        if offset >= self.block.len() {
+            return Ok(0);
+        }
+
+        let block = &self.block[offset..];
+        let size = buf.len().min(block.len());
+        buf[..size].copy_from_slice(&block[..size]);
+        self.cursor += size as u64;
+        Ok(size)
+    }
+}
+
+impl<R> Seek for JooxDump<R>
+where
+    R: Read + Seek,
+{
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        let position = match pos {
+            SeekFrom::Start(p) => Some(p),
+            SeekFrom::Current(p) => self.cursor.checked_add_signed(p),
+            SeekFrom::End(p) => {
+                // Only the last block is decrypted to get the length of music
+                let length = self.reader.seek(SeekFrom::End(0))? - HEADER_SIZE as u64;
+                let blocks = length / ENCRYPTED_BLOCK_SIZE;
+                self.reader.seek(SeekFrom::Start(
+                    HEADER_SIZE as u64 + blocks * ENCRYPTED_BLOCK_SIZE,
+                ))?;
+                self.block_start = blocks * PLAIN_BLOCK_SIZE;
+                self.block.clear();
+                self.next_block()?;
+                (self.block_start + self.block.len() as u64).checked_add_signed(p)
+            }
+        }
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidInput, "invalid seek"))?;
+
+        let index = position / PLAIN_BLOCK_SIZE;
+        self.reader.seek(SeekFrom::Start(
+            HEADER_SIZE as u64 + index * ENCRYPTED_BLOCK_SIZE,
+        ))?;
+        self.block_start = index * PLAIN_BLOCK_SIZE;
+        self.block.clear();
+        self.cursor = position;
+        Ok(self.cursor)
+    }
+}
+
+impl<R> Dump for JooxDump<R> where R: Read + Seek {}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::io::Cursor;
+
+    use anyhow::Result;
+    use cipher::BlockEncrypt;
+
+    use super::*;
+
+    const UUID: &str = "0123456789abcdef0123456789abcdef";
+
+    /// Build a joox file with the blocks encrypted.
+    fn build_file(data: &[u8]) -> Vec<u8> {
+        let cipher = Aes128::new(&JooxDump::<File>::derive_key(UUID).into());
+        let mut file = b"E!04".to_vec();
+        file.extend_from_slice(&(data.len() as u64).to_be_bytes());
+        for block in data.chunks(PLAIN_BLOCK_SIZE as usize) {
+            file.extend_from_slice(&cipher.encrypt_padded_vec::<Pkcs7>(block));
+        }
+        file
+    }
+
+    #[test]
+    fn test_derive_key_ok() {
+        let key = JooxDump::<File>::derive_key(UUID);
+        assert_eq!(
+            key,
+            [
+                0x77, 0x69, 0x1B, 0x43, 0xD9, 0xB1, 0xAF, 0xA8, 0x87, 0x6F, 0xC8, 0xAD, 0x35, 0xD0,
+                0x24, 0x68,
+            ],
+        );
+    }
+
+    #[test]
+    fn test_jooxdump_get_data_ok() -> Result<()> {
+        let mut joox = JooxDump::from_reader(File::open("res/test.ofl_en")?, UUID)?;
+        let data = joox.get_data()?;
+        // The padding of the last block is removed
+        assert_eq!(data.len(), 4096);
+        assert!(data.starts_with(b"fLaC"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_jooxdump_multi_block_ok() -> Result<()> {
+        let data = (0..PLAIN_BLOCK_SIZE + 100)
+            .map(|i| (i % 251) as u8)
+            .collect::<Vec<u8>>();
+        let mut joox = JooxDump::from_reader(Cursor::new(build_file(&data)), UUID)?;
+        assert_eq!(joox.get_data()?, data);
+
+        let mut buf = [0; 8];
+        joox.seek(SeekFrom::Start(PLAIN_BLOCK_SIZE - 4))?;
+        joox.read_exact(&mut buf)?;
+        assert_eq!(buf, data[PLAIN_BLOCK_SIZE as usize - 4..][..8]);
+
+        let position = joox.seek(SeekFrom::End(-10))?;
+        assert_eq!(position, PLAIN_BLOCK_SIZE + 90);
+        joox.read_exact(&mut buf)?;
+        assert_eq!(buf, data[position as usize..][..8]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_jooxdump_err() -> Result<()> {
+        let result = JooxDump::from_reader(Cursor::new(b"E!03\0\0\0\0\0\0\0\0"), UUID);
+        assert!(matches!(result, Err(Errors::InvalidFileType)));
+
+        let file = File::open("res/test.ofl_en")?;
+        let mut joox = JooxDump::from_reader(file, "fedcba9876543210fedcba9876543210")?;
+        assert!(joox.get_data().is_err());
+        Ok(())
+    }
+}
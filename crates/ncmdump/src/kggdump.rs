@@ -0,0 +1,274 @@
+use std::collections::HashMap;
+use std::io::{Read, Seek, SeekFrom, Write};
+
+use serde::Deserialize;
+
+use crate::dump::Dump;
+use crate::ekey;
+use crate::error::{Errors, Result};
+use crate::raw::qmc::Cipher;
+
+const BUFFER_SIZE: usize = 8192;
+const HEADER_SIZE: usize = 0x48;
+const MAX_HASH_LENGTH: usize = 0x100;
+
+const KGG_HEADER: [u8; 16] = [
+    0x7C, 0xD5, 0x32, 0xEB, 0x86, 0x02, 0x7F, 0x4B, 0xA8, 0xAF, 0xA6, 0x8E, 0x0F, 0xFF, 0x99, 0x14,
+];
+
+/// The row of `ShareFileItems` table in `KGMusicV3.db`.
+#[derive(Debug, Deserialize)]
+struct KeyItem {
+    #[serde(rename = "EncryptionKeyId")]
+    hash: Option<String>,
+    #[serde(rename = "EncryptionKey")]
+    ekey: Option<String>,
+}
+
+/// Load the map from audio hash to ekey, which is exported from `KGMusicV3.db`.
+///
+/// The database of client is the sqlcipher database, it should be decrypted
+/// and exported as json first, such as:
+///
+/// ```shell
+/// sqlite3 -json KGMusicV3.db "SELECT EncryptionKeyId, EncryptionKey FROM ShareFileItems" > KGMusicV3.json
+/// ```
+///
+/// # Example
+///
+/// ```rust
+/// # use std::fs::File;
+/// #
+/// # use ncmdump::load_kgg_keys;
+/// #
+/// let file = File::open("res/KGMusicV3.json").expect("Can't open file");
+/// let keys = load_kgg_keys(file).unwrap();
+/// assert!(keys.contains_key("d41d8cd98f00b204e9800998ecf8427e"));
+/// ```
+pub fn load_kgg_keys<R>(reader: R) -> Result<HashMap<String, String>>
+where
+    R: Read,
+{
+    let items =
+        serde_json::from_reader::<R, Vec<KeyItem>>(reader).map_err(|_| Errors::InfoDecodeError)?;
+    let keys = items
+        .into_iter()
+        .filter_map(|item| match (item.hash, item.ekey) {
+            (Some(hash), Some(ekey)) if !hash.is_empty() && !ekey.is_empty() => Some((hash, ekey)),
+            _ => None,
+        })
+        .collect();
+    Ok(keys)
+}
+
+/// The kgg file dump wrapper.
+///
+/// The kgg file only has the hash of audio, the key should be found in the database of client.
+pub struct KggDump<S>
+where
+    S: Read,
+{
+    reader: S,
+    cursor: u64,
+    offset: u64,
+    cipher: Cipher,
+}
+
+impl<S> KggDump<S>
+where
+    S: Read + Seek,
+{
+    /// Create KggDump from a seekable reader and the map from audio hash to ekey.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use std::fs::File;
+    /// #
+    /// # use ncmdump::{load_kgg_keys, KggDump};
+    /// #
+    /// let keys = load_kgg_keys(File::open("res/KGMusicV3.json").unwrap()).unwrap();
+    /// let file = File::open("res/test.kgg").expect("Can't open file");
+    /// let _ = KggDump::from_reader(file, &keys).unwrap();
+    /// ```
+    pub fn from_reader(mut reader: S, keys: &HashMap<String, String>) -> Result<Self> {
+        let mut header = [0; HEADER_SIZE];
+        reader
+            .read_exact(&mut header)
+            .map_err(|_| Errors::InvalidFileType)?;
+        if header[..16] != KGG_HEADER {
+            return Err(Errors::InvalidFileType);
+        }
+
+        let offset = u32::from_le_bytes([header[0x10], header[0x11], header[0x12], header[0x13]]);
+        let version = u32::from_le_bytes([header[0x14], header[0x15], header[0x16], header[0x17]]);
+        if version != 5 {
+            return Err(Errors::UnsupportedVersion);
+        }
+
+        let hash_length =
+            u32::from_le_bytes([header[0x44], header[0x45], header[0x46], header[0x47]]) as usize;
+        if hash_length > MAX_HASH_LENGTH {
+            return Err(Errors::InvalidFileType);
+        }
+        let mut hash = vec![0; hash_length];
+        reader
+            .read_exact(&mut hash)
+            .map_err(|_| Errors::InvalidFileType)?;
+        let hash = String::from_utf8(hash).map_err(|_| Errors::InvalidFileType)?;
+
+        let ekey = keys.get(&hash).ok_or(Errors::KeyNotFound)?;
+        let key = ekey::decrypt(ekey.as_bytes())?;
+        let cipher = Cipher::new(&key)?;
+
+        let offset = offset as u64;
+        reader.seek(SeekFrom::Start(offset))?;
+        Ok(Self {
+            reader,
+            cursor: 0,
+            offset,
+            cipher,
+        })
+    }
+
+    /// Get the music data from kggdump.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use std::fs::File;
+    /// use std::io::Write;
+    ///
+    /// use anyhow::Result;
+    /// use ncmdump::{load_kgg_keys, KggDump};
+    ///
+    /// fn main() -> Result<()> {
+    ///     let keys = load_kgg_keys(File::open("res/KGMusicV3.json")?)?;
+    ///     let file = File::open("res/test.kgg")?;
+    ///     let mut kgg = KggDump::from_reader(file, &keys)?;
+    ///     let music = kgg.get_data()?;
+    ///
+    ///     let mut target = File::options()
+    ///         .create(true)
+    ///         .write(true)
+    ///         .open("res/test.flac")?;
+    ///     target.write_all(&music)?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn get_data(&mut self) -> Result<Vec<u8>> {
+        let mut data = Vec::new();
+        let mut buffer = [0; BUFFER_SIZE];
+        loop {
+            let size = self.read(&mut buffer)?;
+            if size == 0 {
+                break;
+            }
+            data.write_all(&buffer[..size])?;
+        }
+        Ok(data)
+    }
+}
+
+impl<R> Read for KggDump<R>
+where
+    R: Read + Seek,
+{
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let size = self.reader.read(buf)?;
+        self.cipher.encrypt(self.cursor, &mut buf[..size]);
+        self.cursor += size as u64;
+        Ok(size)
+    }
+}
+
+impl<R> Seek for KggDump<R>
+where
+    R: Read + Seek,
+{
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        let pos = match pos {
+            SeekFrom::Start(p) => SeekFrom::Start(p.checked_add(self.offset).ok_or_else(|| {
+                std::io::Error::new(std::io::ErrorKind::InvalidInput, "invalid seek")
+            })?),
+            _ => pos,
+        };
+        self.cursor = self
+            .reader
+            .seek(pos)?
+            .checked_sub(self.offset)
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidInput, "invalid seek"))?;
+        Ok(self.cursor)
+    }
+}
+
+impl<R> Dump for KggDump<R> where R: Read + Seek {}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::io::Cursor;
+
+    use anyhow::Result;
+
+    use super::*;
+
+    const HASH: &str = "d41d8cd98f00b204e9800998ecf8427e";
+    const EKEY: &str = "VGVzdEtleUbj/fFgGTIpxEXpSJ9uJiJLj5ZZm7TgLk+1bMB+Sqxb9Q==";
+
+    fn build_keys() -> HashMap<String, String> {
+        HashMap::from([(HASH.to_string(), EKEY.to_string())])
+    }
+
+    #[test]
+    fn test_load_kgg_keys_ok() -> Result<()> {
+        let keys = load_kgg_keys(File::open("res/KGMusicV3.json")?)?;
+        assert_eq!(keys, build_keys());
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_kgg_keys_err() {
+        let result = load_kgg_keys(&b"{}"[..]);
+        assert!(matches!(result, Err(Errors::InfoDecodeError)));
+    }
+
+    #[test]
+    fn test_kggdump_get_data_ok() -> Result<()> {
+        let mut kgg = KggDump::from_reader(File::open("res/test.kgg")?, &build_keys())?;
+        let data = kgg.get_data()?;
+        assert_eq!(data.len(), 4096);
+        assert_eq!(data[..4], *b"fLaC");
+        assert_eq!(
+            kgg.get_audio_format()?,
+            Some(crate::audio::AudioFormat::Flac)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_kggdump_seek_ok() -> Result<()> {
+        let mut kgg = KggDump::from_reader(File::open("res/test.kgg")?, &build_keys())?;
+        let mut buf = [0; 4];
+        kgg.seek(SeekFrom::Start(1))?;
+        let size = kgg.read(&mut buf)?;
+        assert_eq!(size, 4);
+        assert_eq!(buf, [0x4C, 0x61, 0x43, 0x00]);
+        assert!(kgg.seek(SeekFrom::Current(-16)).is_err());
+        assert!(kgg.seek(SeekFrom::Start(u64::MAX)).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_kggdump_err() -> Result<()> {
+        let result = KggDump::from_reader(File::open("res/test.kgg")?, &HashMap::new());
+        assert!(matches!(result, Err(Errors::KeyNotFound)));
+
+        let result = KggDump::from_reader(File::open("res/test.kgm")?, &build_keys());
+        assert!(matches!(result, Err(Errors::UnsupportedVersion)));
+
+        let result = KggDump::from_reader(Cursor::new([0; HEADER_SIZE]), &build_keys());
+        assert!(matches!(result, Err(Errors::InvalidFileType)));
+        Ok(())
+    }
+}
@@ -0,0 +1,297 @@
+use std::io::{Read, Seek, SeekFrom, Write};
+
+use md5::{Digest, Md5};
+
+use crate::dump::Dump;
+use crate::error::{Errors, Result};
+
+const BUFFER_SIZE: usize = 8192;
+const HEADER_SIZE: usize = 0x3C;
+
+const KGM_HEADER: [u8; 16] = [
+    0x7C, 0xD5, 0x32, 0xEB, 0x86, 0x02, 0x7F, 0x4B, 0xA8, 0xAF, 0xA6, 0x8E, 0x0F, 0xFF, 0x99, 0x14,
+];
+const VPR_HEADER: [u8; 16] = [
+    0x05, 0x28, 0xBC, 0x96, 0xE9, 0xE4, 0x5A, 0x43, 0x91, 0xAA, 0xBD, 0xD0, 0x7A, 0xF5, 0x36, 0x31,
+];
+const VPR_MASK: [u8; 17] = [
+    0x25, 0xDF, 0xE8, 0xA6, 0x75, 0x1E, 0x75, 0x0E, 0x2F, 0x80, 0xF3, 0x2D, 0xB8, 0xB6, 0xE3, 0x11,
+    0x00,
+];
+
+/// The mask table of key slots, the slot of file is recorded in the header.
+const SLOT_KEYS: [(u32, [u8; 4]); 1] = [(1, [0x6C, 0x2C, 0x2F, 0x27])];
+
+/// The kgm file dump wrapper, it's also used for the vpr file.
+pub struct KgmDump<S>
+where
+    S: Read,
+{
+    reader: S,
+    cursor: u64,
+    offset: u64,
+    slot_box: [u8; 16],
+    file_box: [u8; 17],
+    vpr: bool,
+}
+
+impl<S> KgmDump<S>
+where
+    S: Read,
+{
+    /// The md5 digest which is reversed by every two bytes.
+    fn kugou_md5(data: &[u8]) -> [u8; 16] {
+        let digest = Md5::digest(data);
+        let mut result = [0; 16];
+        for i in (0..16).step_by(2) {
+            result[i] = digest[14 - i];
+            result[i + 1] = digest[15 - i];
+        }
+        result
+    }
+
+    fn encrypt(&self, offset: u64, buffer: &mut [u8]) {
+        for (index, byte) in buffer.iter_mut().enumerate() {
+            let offset = offset + index as u64;
+            let mask = (offset as u32)
+                .to_le_bytes()
+                .iter()
+                .fold(0, |acc, b| acc ^ b);
+            *byte ^= self.file_box[(offset % 17) as usize];
+            *byte ^= *byte << 4;
+            *byte ^= self.slot_box[(offset % 16) as usize];
+            *byte ^= mask;
+            if self.vpr {
+                *byte ^= VPR_MASK[(offset % 17) as usize];
+            }
+        }
+    }
+}
+
+impl<S> KgmDump<S>
+where
+    S: Read + Seek,
+{
+    /// Create KgmDump from a seekable reader.
+    /// Both the kgm file and the vpr file are accepted.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use std::fs::File;
+    /// #
+    /// # use ncmdump::KgmDump;
+    /// #
+    /// let file = File::open("res/test.kgm").expect("Can't open file");
+    /// let _ = KgmDump::from_reader(file).unwrap();
+    /// ```
+    pub fn from_reader(mut reader: S) -> Result<Self> {
+        let mut header = [0; HEADER_SIZE];
+        reader
+            .read_exact(&mut header)
+            .map_err(|_| Errors::InvalidFileType)?;
+        let vpr = match header[..16] {
+            ref magic if magic == KGM_HEADER => false,
+            ref magic if magic == VPR_HEADER => true,
+            _ => return Err(Errors::InvalidFileType),
+        };
+
+        let offset = u32::from_le_bytes([header[0x10], header[0x11], header[0x12], header[0x13]]);
+        let version = u32::from_le_bytes([header[0x14], header[0x15], header[0x16], header[0x17]]);
+        let slot = u32::from_le_bytes([header[0x18], header[0x19], header[0x1A], header[0x1B]]);
+        if version != 3 {
+            return Err(Errors::UnsupportedVersion);
+        }
+        let (_, slot_key) = SLOT_KEYS
+            .iter()
+            .find(|(id, _)| *id == slot)
+            .ok_or(Errors::KeyNotFound)?;
+
+        let mut file_box = [0x6B; 17];
+        file_box[..16].copy_from_slice(&Self::kugou_md5(&header[0x2C..0x3C]));
+
+        let offset = offset as u64;
+        reader.seek(SeekFrom::Start(offset))?;
+        Ok(Self {
+            reader,
+            cursor: 0,
+            offset,
+            slot_box: Self::kugou_md5(slot_key),
+            file_box,
+            vpr,
+        })
+    }
+
+    /// Get the music data from kgmdump.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use std::fs::File;
+    /// use std::io::Write;
+    ///
+    /// use anyhow::Result;
+    /// use ncmdump::KgmDump;
+    ///
+    /// fn main() -> Result<()> {
+    ///     let file = File::open("res/test.kgm")?;
+    ///     let mut kgm = KgmDump::from_reader(file)?;
+    ///     let music = kgm.get_data()?;
+    ///
+    ///     let mut target = File::options()
+    ///         .create(true)
+    ///         .write(true)
+    ///         .open("res/test.flac")?;
+    ///     target.write_all(&music)?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn get_data(&mut self) -> Result<Vec<u8>> {
+        let mut data = Vec::new();
+        let mut buffer = [0; BUFFER_SIZE];
+        loop {
+            let size = self.read(&mut buffer)?;
+            if size == 0 {
+                break;
+            }
+            data.write_all(&buffer[..size])?;
+        }
+        Ok(data)
+    }
+}
+
+impl<R> Read for KgmDump<R>
+where
+    R: Read + Seek,
+{
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let size = self.reader.read(buf)?;
+        self.encrypt(self.cursor, &mut buf[..size]);
+        self.cursor += size as u64;
+        Ok(size)
+    }
+}
+
+impl<R> Seek for KgmDump<R>
+where
+    R: Read + Seek,
+{
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        let pos = match pos {
+            SeekFrom::Start(p) => SeekFrom::Start(p.checked_add(self.offset).ok_or_else(|| {
+                std::io::Error::new(std::io::ErrorKind::InvalidInput, "invalid seek")
+            })?),
+            _ => pos,
+        };
+        self.cursor = self
+            .reader
+            .seek(pos)?
+            .checked_sub(self.offset)
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidInput, "invalid seek"))?;
+        Ok(self.cursor)
+    }
+}
+
+impl<R> Dump for KgmDump<R> where R: Read + Seek {}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use anyhow::Result;
+
+    use super::*;
+
+    const FILE_KEY: [u8; 16] = [
+        0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0A, 0x0B, 0x0C, 0x0D, 0x0E,
+        0x0F,
+    ];
+
+    /// Build a kgm file with the reverse of `encrypt`.
+    fn build_file(magic: &[u8; 16], version: u32, data: &[u8]) -> Result<Vec<u8>> {
+        let mut header = Vec::new();
+        header.extend_from_slice(magic);
+        header.extend_from_slice(&0x400u32.to_le_bytes());
+        header.extend_from_slice(&version.to_le_bytes());
+        header.extend_from_slice(&1u32.to_le_bytes());
+        header.extend_from_slice(&[0; 16]);
+        header.extend_from_slice(&FILE_KEY);
+        header.resize(0x400, 0);
+        if version != 3 {
+            return Ok(header);
+        }
+
+        let dump = KgmDump::from_reader(Cursor::new(header.clone()))?;
+        let mut file = header;
+        for (offset, &byte) in data.iter().enumerate() {
+            let mask = (offset as u32)
+                .to_le_bytes()
+                .iter()
+                .fold(0, |acc, b| acc ^ b);
+            let mut value = byte ^ mask ^ dump.slot_box[offset % 16];
+            if dump.vpr {
+                value ^= VPR_MASK[offset % 17];
+            }
+            value ^= (value & 0x0F) << 4;
+            file.push(value ^ dump.file_box[offset % 17]);
+        }
+        Ok(file)
+    }
+
+    #[test]
+    fn test_kugou_md5_ok() {
+        let result = KgmDump::<Cursor<Vec<u8>>>::kugou_md5(b"");
+        assert_eq!(
+            result,
+            [
+                0x42, 0x7E, 0xEC, 0xF8, 0x09, 0x98, 0xE9, 0x80, 0xB2, 0x04, 0x8F, 0x00, 0x8C, 0xD9,
+                0xD4, 0x1D,
+            ],
+        );
+    }
+
+    #[test]
+    fn test_kgmdump_get_data_ok() -> Result<()> {
+        let file = build_file(&KGM_HEADER, 3, b"fLaC\x00\x00\x00\x22")?;
+        let mut kgm = KgmDump::from_reader(Cursor::new(file))?;
+        assert!(!kgm.vpr);
+        let data = kgm.get_data()?;
+        assert_eq!(data, b"fLaC\x00\x00\x00\x22");
+        Ok(())
+    }
+
+    #[test]
+    fn test_kgmdump_vpr_get_data_ok() -> Result<()> {
+        let file = build_file(&VPR_HEADER, 3, b"fLaC\x00\x00\x00\x22")?;
+        let mut kgm = KgmDump::from_reader(Cursor::new(file))?;
+        assert!(kgm.vpr);
+        let data = kgm.get_data()?;
+        assert_eq!(data, b"fLaC\x00\x00\x00\x22");
+        Ok(())
+    }
+
+    #[test]
+    fn test_kgmdump_seek_ok() -> Result<()> {
+        let file = build_file(&KGM_HEADER, 3, b"fLaC\x00\x00\x00\x22")?;
+        let mut kgm = KgmDump::from_reader(Cursor::new(file))?;
+        let mut buf = [0; 4];
+        kgm.seek(SeekFrom::Start(4))?;
+        let size = kgm.read(&mut buf)?;
+        assert_eq!(size, 4);
+        assert_eq!(buf, [0x00, 0x00, 0x00, 0x22]);
+        assert!(kgm.seek(SeekFrom::Current(-16)).is_err());
+        assert!(kgm.seek(SeekFrom::Start(u64::MAX)).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_kgmdump_err() -> Result<()> {
+        let file = build_file(&KGM_HEADER, 2, b"fLaC")?;
+        let result = KgmDump::from_reader(Cursor::new(file));
+        assert!(matches!(result, Err(Errors::UnsupportedVersion)));
+
+        let result = KgmDump::from_reader(Cursor::new([0; 0x3C]));
+        assert!(matches!(result, Err(Errors::InvalidFileType)));
+        Ok(())
+    }
+}
@@ -0,0 +1,207 @@
+use std::io::{Read, Seek, SeekFrom, Write};
+
+use crate::dump::Dump;
+use crate::error::{Errors, Result};
+
+const BUFFER_SIZE: usize = 8192;
+const HEADER_SIZE: usize = 0x400;
+const KEY: &[u8; 32] = b"MoOtOiTvINGwd2E6n0E1i7L5t2IoOoNk";
+
+/// The kwm file dump wrapper.
+pub struct KwmDump<S>
+where
+    S: Read,
+{
+    reader: S,
+    cursor: u64,
+    mask: [u8; 32],
+}
+
+impl<S> KwmDump<S>
+where
+    S: Read,
+{
+    /// Build the mask from the resource id in header.
+    fn build_mask(resource_id: u64) -> [u8; 32] {
+        let id = resource_id.to_string().into_bytes();
+        let mut mask = *KEY;
+        for (index, byte) in mask.iter_mut().enumerate() {
+            *byte ^= id[index % id.len()];
+        }
+        mask
+    }
+
+    /// Check the file format by header.
+    fn check_format(buffer: &[u8]) -> bool {
+        buffer.starts_with(b"yeelion-kuwo-tme") || buffer.starts_with(b"yeelion-kuwo\0\0\0\0")
+    }
+
+    fn encrypt(&self, offset: u64, buffer: &mut [u8]) {
+        for (index, byte) in buffer.iter_mut().enumerate() {
+            *byte ^= self.mask[((offset + index as u64) & 0x1F) as usize];
+        }
+    }
+
+    /// Create KwmDump from reader.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use std::fs::File;
+    /// #
+    /// # use ncmdump::KwmDump;
+    /// #
+    /// let file = File::open("res/test.kwm").expect("Can't open file");
+    /// let _ = KwmDump::from_reader(file).unwrap();
+    /// ```
+    pub fn from_reader(mut reader: S) -> Result<Self> {
+        let mut header = [0; HEADER_SIZE];
+        reader
+            .read_exact(&mut header)
+            .map_err(|_| Errors::InvalidFileType)?;
+        if !Self::check_format(&header) {
+            return Err(Errors::InvalidFileType);
+        }
+
+        let mut resource_id = [0; 8];
+        resource_id.copy_from_slice(&header[0x18..0x20]);
+        Ok(Self {
+            reader,
+            cursor: 0,
+            mask: Self::build_mask(u64::from_le_bytes(resource_id)),
+        })
+    }
+
+    /// Get the music data from kwmdump.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use std::fs::File;
+    /// use std::io::Write;
+    ///
+    /// use anyhow::Result;
+    /// use ncmdump::KwmDump;
+    ///
+    /// fn main() -> Result<()> {
+    ///     let file = File::open("res/test.kwm")?;
+    ///     let mut kwm = KwmDump::from_reader(file)?;
+    ///     let music = kwm.get_data()?;
+    ///
+    ///     let mut target = File::options()
+    ///         .create(true)
+    ///         .write(true)
+    ///         .open("res/test.flac")?;
+    ///     target.write_all(&music)?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn get_data(&mut self) -> std::io::Result<Vec<u8>> {
+        let mut buffer = [0; BUFFER_SIZE];
+        let mut output = Vec::new();
+        loop {
+            let size = self.read(&mut buffer)?;
+            if size == 0 {
+                break;
+            }
+            output.write_all(&buffer[..size])?;
+        }
+        Ok(output)
+    }
+}
+
+impl<R> Read for KwmDump<R>
+where
+    R: Read,
+{
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let size = self.reader.read(buf)?;
+        self.encrypt(self.cursor, &mut buf[..size]);
+        self.cursor += size as u64;
+        Ok(size)
+    }
+}
+
+impl<R> Seek for KwmDump<R>
+where
+    R: Read + Seek,
+{
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        let base = HEADER_SIZE as u64;
+        let pos = match pos {
+            SeekFrom::Start(p) => SeekFrom::Start(p.checked_add(base).ok_or_else(|| {
+                std::io::Error::new(std::io::ErrorKind::InvalidInput, "invalid seek")
+            })?),
+            _ => pos,
+        };
+        self.cursor =
+            self.reader.seek(pos)?.checked_sub(base).ok_or_else(|| {
+                std::io::Error::new(std::io::ErrorKind::InvalidInput, "invalid seek")
+            })?;
+        Ok(self.cursor)
+    }
+}
+
+impl<R> Dump for KwmDump<R> where R: Read + Seek {}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::io::Cursor;
+
+    use anyhow::Result;
+
+    use super::*;
+
+    #[test]
+    fn test_build_mask_ok() {
+        let mask = KwmDump::<File>::build_mask(123456789);
+        assert_eq!(
+            mask,
+            [
+                0x7C, 0x5D, 0x7C, 0x40, 0x7A, 0x5F, 0x63, 0x4E, 0x70, 0x7F, 0x75, 0x44, 0x50, 0x07,
+                0x73, 0x01, 0x56, 0x09, 0x74, 0x03, 0x5A, 0x03, 0x79, 0x03, 0x43, 0x0A, 0x70, 0x5E,
+                0x7D, 0x5C, 0x7A, 0x5E,
+            ],
+        );
+    }
+
+    #[test]
+    fn test_check_format_ok() {
+        assert!(KwmDump::<File>::check_format(b"yeelion-kuwo-tme"));
+        assert!(KwmDump::<File>::check_format(b"yeelion-kuwo\0\0\0\0"));
+        assert!(!KwmDump::<File>::check_format(b"yeelion-kuwo"));
+    }
+
+    #[test]
+    fn test_kwmdump_get_data_ok() -> Result<()> {
+        let mut kwm = KwmDump::from_reader(File::open("res/test.kwm")?)?;
+        let data = kwm.get_data()?;
+        assert_eq!(data.len(), 4096);
+        // The flac magic and the header of stream info block
+        assert_eq!(data[..8], *b"fLaC\x00\x00\x00\x22");
+        Ok(())
+    }
+
+    #[test]
+    fn test_kwmdump_seek_ok() -> Result<()> {
+        let mut kwm = KwmDump::from_reader(File::open("res/test.kwm")?)?;
+        let mut buf = [0; 4];
+        kwm.seek(SeekFrom::Start(1))?;
+        let size = kwm.read(&mut buf)?;
+        assert_eq!(size, 4);
+        assert_eq!(buf, [0x4C, 0x61, 0x43, 0x00]);
+        assert!(kwm.seek(SeekFrom::Current(-16)).is_err());
+        assert!(kwm.seek(SeekFrom::Start(u64::MAX)).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_kwmdump_err() {
+        let result = KwmDump::from_reader(Cursor::new([0; HEADER_SIZE]));
+        assert!(matches!(result, Err(Errors::InvalidFileType)));
+
+        let result = KwmDump::from_reader(Cursor::new(b"yeelion-kuwo-tme"));
+        assert!(matches!(result, Err(Errors::InvalidFileType)));
+    }
+}
@@ -37,21 +37,100 @@
 //! }
 //! ```
 //!
-#[cfg(feature = "ncmdump")]
+//! # Features
+//!
+//! Only the ncm and qmc dumpers are enabled by default, the others are opt-in:
+//!
+//! ```toml
+//! ncmdump = { version = "0.8.0", features = ["kgmdump", "kwmdump", "xmdump"] }
+//! ```
+//!
+//! # `no_std`
+//!
+//! The ciphers and the ncm header parser in [`raw`] only need `alloc`.
+//! Disable the default features to use them without `std`:
+//!
+//! ```toml
+//! ncmdump = { version = "0.8.0", default-features = false, features = ["ncmdump", "qmcdump"] }
+//! ```
+//!
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
+#[cfg(all(feature = "async", feature = "ncmdump"))]
+pub use crate::asyncdump::AsyncNcmDump;
+#[cfg(all(feature = "async", feature = "qmcdump"))]
+pub use crate::asyncdump::AsyncQmcDump;
+#[cfg(feature = "std")]
+pub use crate::dump::{Dump, NcmImage, NcmImageKind};
+#[cfg(feature = "jooxdump")]
+pub use crate::jooxdump::JooxDump;
+#[cfg(feature = "kggdump")]
+pub use crate::kggdump::{load_kgg_keys, KggDump};
+#[cfg(feature = "kgmdump")]
+pub use crate::kgmdump::KgmDump;
+#[cfg(feature = "kwmdump")]
+pub use crate::kwmdump::KwmDump;
+#[cfg(all(feature = "std", feature = "ncmdump"))]
 pub use crate::ncmdump::NcmDump;
 #[deprecated = "Rename as NcmDump"]
-#[cfg(feature = "ncmdump")]
+#[cfg(all(feature = "std", feature = "ncmdump"))]
 pub use crate::ncmdump::NcmDump as Ncmdump;
-#[cfg(feature = "ncmdump")]
-pub use crate::ncmdump::NcmInfo;
-#[cfg(feature = "qmcdump")]
+#[cfg(all(feature = "std", feature = "ncmdump"))]
+pub use crate::ncmdump::NcmStreamDump;
+#[cfg(all(feature = "std", feature = "ncmdump"))]
+pub use crate::ncmdump::NcmWriter;
+#[cfg(all(feature = "std", feature = "ncmdump"))]
+pub use crate::ncmdump::UcDump;
+#[cfg(feature = "utils")]
+pub use crate::open::{open, open_as, open_path};
+#[cfg(all(feature = "std", feature = "qmcdump"))]
 pub use crate::qmcdump::QmcDump;
-
+#[cfg(all(feature = "std", feature = "qmcdump"))]
+pub use crate::qmcdump::QmcV2Dump;
+#[cfg(all(feature = "std", feature = "qmcdump"))]
+pub use crate::qmcdump::TmDump;
+#[cfg(all(feature = "std", feature = "qmcdump"))]
+pub use crate::qmcdump::{QmcTrailer, QmcTrailerKind};
 #[cfg(feature = "ncmdump")]
+pub use crate::raw::ncm::{NcmHeader, NcmInfo};
+#[cfg(feature = "utils")]
+pub use crate::registry::{Format, ReadSeek, Registry};
+#[cfg(feature = "xmdump")]
+pub use crate::xmdump::XmDump;
+#[cfg(feature = "xmlydump")]
+pub use crate::xmlydump::{XmlyDump, XmlyKind};
+
+#[cfg(all(feature = "async", any(feature = "ncmdump", feature = "qmcdump")))]
+mod asyncdump;
+#[cfg(feature = "std")]
+mod dump;
+#[cfg(feature = "jooxdump")]
+mod jooxdump;
+#[cfg(feature = "kggdump")]
+mod kggdump;
+#[cfg(feature = "kgmdump")]
+mod kgmdump;
+#[cfg(feature = "kwmdump")]
+mod kwmdump;
+#[cfg(all(feature = "std", feature = "ncmdump"))]
 mod ncmdump;
-#[cfg(feature = "qmcdump")]
+#[cfg(feature = "utils")]
+mod open;
+#[cfg(all(feature = "std", feature = "qmcdump"))]
 mod qmcdump;
+#[cfg(feature = "utils")]
+mod registry;
+#[cfg(feature = "xmdump")]
+mod xmdump;
+#[cfg(feature = "xmlydump")]
+mod xmlydump;
 
+pub mod audio;
+#[cfg(all(feature = "std", feature = "qmcdump"))]
+pub mod ekey;
 pub mod error;
+pub mod raw;
 #[cfg(feature = "utils")]
 pub mod utils;
@@ -1,75 +1,11 @@
 use std::io::{Read, Seek, SeekFrom, Write};
 
-use aes::Aes128;
-use base64::engine::general_purpose::STANDARD;
-use base64::Engine;
-use cipher::block_padding::Pkcs7;
-use cipher::{BlockDecryptMut, KeyInit};
-use serde::{Deserialize, Serialize};
-
+use crate::dump::{Dump, NcmImage, NcmImageKind};
 use crate::error::{Errors, Result};
+use crate::raw::ncm::{self, NcmHeader, NcmInfo, RawUcIndex};
+use crate::raw::Event;
 
-const HEADER_KEY: [u8; 16] = [
-    0x68, 0x7A, 0x48, 0x52, 0x41, 0x6D, 0x73, 0x6F, 0x35, 0x6B, 0x49, 0x6E, 0x62, 0x61, 0x78, 0x57,
-];
-
-const INFO_KEY: [u8; 16] = [
-    0x23, 0x31, 0x34, 0x6C, 0x6A, 0x6B, 0x5F, 0x21, 0x5C, 0x5D, 0x26, 0x30, 0x55, 0x3C, 0x27, 0x28,
-];
-
-#[derive(Debug, Deserialize, Eq, PartialEq, Serialize)]
-#[serde(untagged)]
-pub enum NcmId {
-    String(String),
-    Integer(u64),
-}
-
-/// The ncm file information.
-#[derive(Debug, Deserialize, Eq, PartialEq, Serialize)]
-pub struct RawNcmInfo {
-    /// The name of music
-    #[serde(rename = "musicName")]
-    pub name: String,
-    /// The id of music
-    #[serde(rename = "musicId")]
-    pub id: NcmId,
-    /// The album of music, it's an url
-    pub album: String,
-    /// The artist of music, first item is name, second item is id
-    pub artist: Vec<(String, NcmId)>,
-    // The bit rate of music
-    pub bitrate: NcmId,
-    /// The duration of music
-    pub duration: NcmId,
-    /// The format of music, is maybe 'mp3' or 'flac'
-    pub format: String,
-    /// The id of MV
-    #[serde(rename = "mvId")]
-    pub mv_id: Option<NcmId>,
-    /// The alias of music
-    pub alias: Option<Vec<String>>,
-}
-
-#[derive(Debug, Eq, PartialEq)]
-pub struct NcmInfo {
-    pub name: String,
-    /// The id of music
-    pub id: u64,
-    /// The album of music, it's an url
-    pub album: String,
-    /// The artist of music, first item is name, second item is id
-    pub artist: Vec<(String, u64)>,
-    // The bit rate of music
-    pub bitrate: u64,
-    /// The duration of music
-    pub duration: u64,
-    /// The format of music, is maybe 'mp3' or 'flac'
-    pub format: String,
-    /// The id of MV
-    pub mv_id: Option<u64>,
-    /// The alias of music
-    pub alias: Option<Vec<String>>,
-}
+const UC_KEY: u8 = 0xA3;
 
 /// The ncm file dump wrapper.
 pub struct NcmDump<S>
@@ -78,101 +14,148 @@ where
 {
     reader: S,
     cursor: u64,
-    info: (u64, u64),
-    image: (u64, u64),
-    key_box: [u8; 256],
+    base: u64,
+    header: NcmHeader,
+    sections: NcmSections,
 }
 
-impl From<RawNcmInfo> for NcmInfo {
-    fn from(raw_info: RawNcmInfo) -> Self {
-        Self {
-            name: raw_info.name,
-            id: raw_info.id.get_id().unwrap_or(0),
-            album: raw_info.album,
-            artist: raw_info
-                .artist
-                .into_iter()
-                .map(|(name, id)| (name, id.get_id().unwrap_or(0)))
-                .collect::<Vec<(String, u64)>>(),
-            bitrate: raw_info.bitrate.get_id().unwrap_or(0),
-            duration: raw_info.duration.get_id().unwrap_or(0),
-            format: raw_info.format,
-            mv_id: match raw_info.mv_id {
-                Some(id) => match id.get_id() {
-                    Ok(inner) => Some(inner),
-                    Err(_) => None,
-                },
-                None => None,
-            },
-            alias: raw_info.alias,
-        }
+impl NcmInfo {
+    /// Read the information from the `.idx` file of uc cache file.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use std::fs::File;
+    /// #
+    /// # use ncmdump::NcmInfo;
+    /// #
+    /// let file = File::open("res/test.idx").expect("Can't open file");
+    /// let info = NcmInfo::from_index(file).unwrap();
+    /// println!("{:?}", info);
+    /// ```
+    pub fn from_index<R>(reader: R) -> Result<Self>
+    where
+        R: Read,
+    {
+        let index = serde_json::from_reader::<R, RawUcIndex>(reader)
+            .map_err(|_| Errors::InfoDecodeError)?;
+        Ok(NcmInfo::from(index))
     }
 }
 
-impl NcmId {
-    pub fn get_id(self) -> Result<u64> {
-        match self {
-            NcmId::String(s) => {
-                if s.is_empty() {
-                    return Err(Errors::InfoDecodeError);
-                }
-                s.parse().map_err(|_| Errors::InfoDecodeError)
-            }
-            NcmId::Integer(num) => Ok(num),
+/// The sections of ncm header, which are collected from the events of parser.
+#[derive(Default)]
+pub(crate) struct NcmSections {
+    pub(crate) info: Vec<u8>,
+    pub(crate) cover: Vec<u8>,
+    pub(crate) image: Vec<u8>,
+}
+
+impl NcmSections {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keep the section of event, the music data is returned.
+    pub(crate) fn push(&mut self, event: Event) -> Option<Vec<u8>> {
+        match event {
+            Event::Key | Event::NeedMoreData(_) => {}
+            Event::Info(data) => self.info = data,
+            Event::Cover(data) => self.cover = data,
+            Event::Image(data) => self.image = data,
+            Event::Audio(data) => return Some(data),
         }
+        None
     }
-}
 
-impl<S> NcmDump<S>
-where
-    S: Read,
-{
-    #[inline]
-    fn base(&self) -> u64 {
-        self.image.0 + self.image.1
+    /// Read the header by the parser, the reader stops at the start of music data.
+    pub(crate) fn read<S>(reader: &mut S) -> Result<(NcmHeader, Self)>
+    where
+        S: Read,
+    {
+        let mut parser = NcmSectionsParser::new();
+        while let Some(length) = parser.next_read()? {
+            let mut data = Vec::new();
+            reader.by_ref().take(length as u64).read_to_end(&mut data)?;
+            parser.fill(&data)?;
+        }
+        parser.finish()
     }
 
-    fn get_key(key: &[u8]) -> Result<Vec<u8>> {
-        let key_buffer = key.iter().map(|byte| byte ^ 0x64).collect::<Vec<u8>>();
-        let decrypt_buffer = Self::decrypt(&key_buffer, &HEADER_KEY)?;
-        Ok(decrypt_buffer[17..].to_vec())
+    pub(crate) fn get_info(&self) -> Result<Option<NcmInfo>> {
+        ncm::decode_info(&self.info)
     }
 
-    fn encrypt(&mut self, offset: u64, buffer: &mut [u8]) {
-        for (i, byte) in buffer.iter_mut().enumerate() {
-            let j = ((offset + i as u64 + 1) & 0xff) as usize;
-            let k = (self.key_box[j].wrapping_add(j as u8)) as usize;
-            let key_index = self.key_box[k].wrapping_add(self.key_box[j]) as usize;
-            *byte ^= self.key_box[key_index]
+    pub(crate) fn get_image(&self) -> Option<&[u8]> {
+        match self.cover.is_empty() {
+            true => None,
+            false => Some(&self.cover),
         }
     }
 
-    fn decrypt(data: &[u8], key: &[u8; 16]) -> Result<Vec<u8>> {
-        let result = Aes128::new(key.into())
-            .decrypt_padded_vec_mut::<Pkcs7>(data)
-            .map_err(|_| Errors::DecryptError)?;
-        Ok(result)
+    pub(crate) fn get_images(&self) -> Vec<NcmImage> {
+        let mut images = Vec::new();
+        if let Some(data) = self.get_image() {
+            images.push(NcmImage {
+                kind: NcmImageKind::Cover,
+                data: data.to_vec(),
+            });
+        }
+        if !self.image.is_empty() {
+            images.push(NcmImage {
+                kind: NcmImageKind::Other,
+                data: self.image.clone(),
+            });
+        }
+        images
     }
+}
 
-    fn build_key_box(key: &[u8]) -> [u8; 256] {
-        let mut j = 0;
-        let mut key_box = [0u8; 256];
-        key_box
-            .iter_mut()
-            .enumerate()
-            .for_each(|(i, k)| *k = i as u8);
+/// The parser of ncm header which collects the sections,
+/// it's driven by the blocking and async front ends with the data they read.
+pub(crate) struct NcmSectionsParser {
+    parser: ncm::Parser,
+    sections: NcmSections,
+    input: Vec<u8>,
+}
 
-        let key_stream = key.iter().cycle();
-        for (i, &k) in (0..256).zip(key_stream) {
-            j = key_box[i].wrapping_add(j).wrapping_add(k);
-            key_box.swap(i, j as usize);
+impl NcmSectionsParser {
+    pub(crate) fn new() -> Self {
+        Self {
+            parser: ncm::Parser::new(),
+            sections: NcmSections::new(),
+            input: Vec::new(),
         }
-        key_box
     }
 
-    /// Check the file format by header.
-    fn check_format(buffer: &[u8]) -> bool {
-        buffer.starts_with(b"CTENFDAM")
+    /// Parse the buffered input, and return the length of data to read next.
+    /// It returns `None` when the parser reaches the start of music data.
+    pub(crate) fn next_read(&mut self) -> Result<Option<usize>> {
+        loop {
+            let (size, event) = self.parser.parse(&self.input)?;
+            self.input.drain(..size);
+            match event {
+                Event::NeedMoreData(_) if self.parser.is_audio() => return Ok(None),
+                Event::NeedMoreData(length) => return Ok(Some(length)),
+                event => {
+                    self.sections.push(event);
+                }
+            }
+        }
+    }
+
+    /// Push the data which is read for [`NcmSectionsParser::next_read`].
+    /// The empty data means the reader ends in the header.
+    pub(crate) fn fill(&mut self, data: &[u8]) -> Result<()> {
+        if data.is_empty() {
+            self.parser.finish()?;
+        }
+        self.input.extend_from_slice(data);
+        Ok(())
+    }
+
+    pub(crate) fn finish(self) -> Result<(NcmHeader, NcmSections)> {
+        Ok((self.parser.into_header()?, self.sections))
     }
 }
 
@@ -209,68 +192,37 @@ where
     /// let _ = NcmDump::from_reader(cursor).unwrap();
     /// ```
     pub fn from_reader(mut reader: S) -> Result<Self> {
-        // check format
-        let mut format = [0; 10];
-        reader
-            .read_exact(&mut format)
-            .map_err(|_| Errors::InvalidFileType)?;
-        if !Self::check_format(&format) {
-            return Err(Errors::InvalidFileType);
-        }
-
-        let mut key_length = [0; 4];
-        reader
-            .read_exact(&mut key_length)
-            .map_err(|_| Errors::InvalidKeyLength)?;
-        let key_length = u32::from_le_bytes(key_length) as usize;
-        let mut key = vec![0u8; key_length];
-        reader
-            .read_exact(&mut key)
-            .map_err(|_| Errors::InvalidKeyLength)?;
-        let key = Self::get_key(&key)?;
-        let key_box = Self::build_key_box(&key);
-
-        // reader.seek(SeekFrom::Current(key_length as i64))?;
-        let mut info_length = [0; 4];
-        reader
-            .read_exact(&mut info_length)
-            .map_err(|_| Errors::InvalidInfoLength)?;
-        let info_start = reader.stream_position()?;
-        let info_length = u32::from_le_bytes(info_length) as u64;
-
-        reader.seek(SeekFrom::Current(info_length as i64))?;
-        reader.seek(SeekFrom::Current(5))?;
-        let mut cover_frame_len = [0; 4];
-        reader.read_exact(&mut cover_frame_len)?;
-        let cover_frame_len = u32::from_le_bytes(cover_frame_len) as u64;
-
-        let mut image_length = [0; 4];
-        reader
-            .read_exact(&mut image_length)
-            .map_err(|_| Errors::InvalidImageLength)?;
-        let image_start = reader.stream_position()?;
-        let image_length = u32::from_le_bytes(image_length) as u64;
-
-        reader.seek(SeekFrom::Start(image_start + cover_frame_len))?;
+        let start = reader.stream_position()?;
+        let (header, sections) = NcmSections::read(&mut reader)?;
         Ok(Self {
             reader,
-            key_box,
             cursor: 0,
-            info: (info_start, info_length),
-            image: (image_start, image_length),
+            base: start + header.audio_offset as u64,
+            header,
+            sections,
         })
     }
 
-    /// Utils for get bytes.
-    fn get_bytes(&mut self, start: u64, length: u64) -> Result<Vec<u8>> {
-        let reader = self.reader.by_ref();
-        let mut buf = Vec::new();
-        reader.seek(SeekFrom::Start(start))?;
-        reader.take(length).read_to_end(&mut buf)?;
-        Ok(buf)
+    /// Get the layout of header, the offsets are from the start of ncm data.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use std::fs::File;
+    /// #
+    /// # use ncmdump::NcmDump;
+    /// #
+    /// let file = File::open("res/test.ncm").expect("Can't open file");
+    /// let ncm = NcmDump::from_reader(file).unwrap();
+    /// let header = ncm.get_header();
+    /// println!("The music data starts at {}", header.audio_offset);
+    /// ```
+    pub fn get_header(&self) -> &NcmHeader {
+        &self.header
     }
 
     /// Decode the information buffer and just return the information.
+    /// It returns `None` if there is no information in the file, which is found in the old version.
     ///
     /// # Example
     ///
@@ -284,27 +236,13 @@ where
     /// fn main() -> Result<()> {
     ///     let file = File::open("res/test.ncm")?;
     ///     let mut ncm = NcmDump::from_reader(file)?;
-    ///     let info = ncm.get_info();
+    ///     let info = ncm.get_info()?;
     ///     println!("{:?}", info);
     ///     Ok(())
     /// }
     /// ```
-    pub fn get_info(&mut self) -> Result<NcmInfo> {
-        let (start, length) = self.info;
-        let info_bytes = self.get_bytes(start, length)?;
-        let info_tmp = info_bytes
-            .iter()
-            .map(|item| item ^ 0x63)
-            .collect::<Vec<u8>>();
-        let info_key = STANDARD
-            .decode(&info_tmp[22..])
-            .map_err(|_| Errors::InfoDecodeError)?;
-        let info_data = Self::decrypt(&info_key, &INFO_KEY)?;
-        let info_str =
-            String::from_utf8(info_data[6..].to_vec()).map_err(|_| Errors::InfoDecodeError)?;
-        let info =
-            serde_json::from_str::<RawNcmInfo>(&info_str).map_err(|_| Errors::InfoDecodeError)?;
-        Ok(NcmInfo::from(info))
+    pub fn get_info(&mut self) -> Result<Option<NcmInfo>> {
+        self.sections.get_info()
     }
 
     /// Get the image bytes from ncmdump, if it's exists.
@@ -322,20 +260,40 @@ where
     ///     use std::io::Write;
     /// let file = File::open("res/test.ncm")?;
     ///     let mut ncm = NcmDump::from_reader(file)?;
-    ///     let image = ncm.get_image()?;
+    ///     if let Some(image) = ncm.get_image()? {
+    ///         let mut target = File::options()
+    ///             .create(true)
+    ///             .write(true)
+    ///             .open("res/test.jpeg")?;
+    ///         target.write_all(&image)?;
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn get_image(&mut self) -> Result<Option<Vec<u8>>> {
+        Ok(self.sections.get_image().map(<[u8]>::to_vec))
+    }
+
+    /// Get every image in the cover frame, the cover is always the first one.
     ///
-    ///     let mut target = File::options()
-    ///         .create(true)
-    ///         .write(true)
-    ///         .open("res/test.jpeg")?;
-    ///     target.write_all(&image)?;
+    /// # Example:
+    ///
+    /// ```rust
+    /// use std::fs::File;
+    ///
+    /// use anyhow::Result;
+    /// use ncmdump::{NcmDump, NcmImageKind};
+    ///
+    /// fn main() -> Result<()> {
+    ///     let file = File::open("res/test.ncm")?;
+    ///     let mut ncm = NcmDump::from_reader(file)?;
+    ///     let images = ncm.get_images()?;
+    ///     assert_eq!(images[0].kind, NcmImageKind::Cover);
     ///     Ok(())
     /// }
     /// ```
-    pub fn get_image(&mut self) -> Result<Vec<u8>> {
-        let (start, end) = self.image;
-        let image = self.get_bytes(start, end)?;
-        Ok(image)
+    pub fn get_images(&mut self) -> Result<Vec<NcmImage>> {
+        Ok(self.sections.get_images())
     }
 
     /// Get the music data from ncmdump.
@@ -366,7 +324,8 @@ where
     pub fn get_data(&mut self) -> Result<Vec<u8>> {
         let mut data = Vec::new();
         let mut buffer = [0; 0x8000];
-        while let Ok(size) = self.read(&mut buffer) {
+        loop {
+            let size = self.read(&mut buffer)?;
             if size == 0 {
                 break;
             }
@@ -382,7 +341,7 @@ where
 {
     fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
         let size = self.reader.read(buf)?;
-        self.encrypt(self.cursor, buf);
+        ncm::encrypt(&self.header.key_box, self.cursor, &mut buf[..size]);
         self.cursor += size as u64;
         Ok(size)
     }
@@ -393,19 +352,391 @@ where
     R: Read + Seek,
 {
     fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
-        let base = self.base();
+        let base = self.base;
         let pos = match pos {
-            SeekFrom::Start(p) => SeekFrom::Start(p + base),
+            SeekFrom::Start(p) => SeekFrom::Start(p.checked_add(base).ok_or_else(|| {
+                std::io::Error::new(std::io::ErrorKind::InvalidInput, "invalid seek")
+            })?),
             _ => pos,
         };
-        self.cursor = self.reader.seek(pos)? - base;
+        self.cursor =
+            self.reader.seek(pos)?.checked_sub(base).ok_or_else(|| {
+                std::io::Error::new(std::io::ErrorKind::InvalidInput, "invalid seek")
+            })?;
+        Ok(self.cursor)
+    }
+}
+
+impl<R> Dump for NcmDump<R>
+where
+    R: Read + Seek,
+{
+    fn get_info(&mut self) -> Result<Option<NcmInfo>> {
+        NcmDump::get_info(self)
+    }
+
+    fn get_cover(&mut self) -> Result<Option<Vec<u8>>> {
+        self.get_image()
+    }
+
+    fn get_images(&mut self) -> Result<Vec<NcmImage>> {
+        NcmDump::get_images(self)
+    }
+}
+
+/// The ncm file dump wrapper for the forward-only reader, such as a pipe or a http body.
+///
+/// The information and the cover frame are kept in memory while parsing the header,
+/// and then the music data is streamed from the reader.
+pub struct NcmStreamDump<S>
+where
+    S: Read,
+{
+    reader: S,
+    cursor: u64,
+    header: NcmHeader,
+    sections: NcmSections,
+}
+
+impl<S> NcmStreamDump<S>
+where
+    S: Read,
+{
+    /// Create a NcmStreamDump from a reader, the reader is only read forward.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use std::fs::File;
+    /// # use std::io::Read;
+    /// #
+    /// # use ncmdump::NcmStreamDump;
+    /// #
+    /// # let mut file = File::open("res/test.ncm").expect("Can't open file.");
+    /// # let mut data = Vec::new();
+    /// # file.read_to_end(&mut data).expect("Can't read file");
+    /// let _ = NcmStreamDump::from_reader(&data[..]).unwrap();
+    /// ```
+    pub fn from_reader(mut reader: S) -> Result<Self> {
+        let (header, sections) = NcmSections::read(&mut reader)?;
+        Ok(Self {
+            reader,
+            cursor: 0,
+            header,
+            sections,
+        })
+    }
+
+    /// Get the layout of header, the offsets are from the start of ncm data.
+    pub fn get_header(&self) -> &NcmHeader {
+        &self.header
+    }
+
+    /// Decode the information buffer and just return the information.
+    /// It returns `None` if there is no information in the file, which is found in the old version.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use std::fs::File;
+    /// #
+    /// # use ncmdump::NcmStreamDump;
+    /// #
+    /// let file = File::open("res/test.ncm").expect("Can't open file");
+    /// let ncm = NcmStreamDump::from_reader(file).unwrap();
+    /// let info = ncm.get_info().unwrap();
+    /// println!("{:?}", info);
+    /// ```
+    pub fn get_info(&self) -> Result<Option<NcmInfo>> {
+        self.sections.get_info()
+    }
+
+    /// Get the image bytes, if it's exists.
+    pub fn get_image(&self) -> Option<&[u8]> {
+        self.sections.get_image()
+    }
+
+    /// Get every image in the cover frame, the cover is always the first one.
+    pub fn get_images(&self) -> Vec<NcmImage> {
+        self.sections.get_images()
+    }
+
+    /// Get the music data from the rest of reader.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use std::fs::File;
+    /// use std::io::Read;
+    ///
+    /// use anyhow::Result;
+    /// use ncmdump::NcmStreamDump;
+    ///
+    /// fn main() -> Result<()> {
+    ///     let mut file = File::open("res/test.ncm")?;
+    ///     let mut data = Vec::new();
+    ///     file.read_to_end(&mut data)?;
+    ///
+    ///     let mut ncm = NcmStreamDump::from_reader(&data[..])?;
+    ///     let music = ncm.get_data()?;
+    ///     assert!(music.starts_with(b"fLaC"));
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn get_data(&mut self) -> Result<Vec<u8>> {
+        let mut data = Vec::new();
+        self.read_to_end(&mut data)?;
+        Ok(data)
+    }
+}
+
+impl<R> Read for NcmStreamDump<R>
+where
+    R: Read,
+{
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let size = self.reader.read(buf)?;
+        ncm::encrypt(&self.header.key_box, self.cursor, &mut buf[..size]);
+        self.cursor += size as u64;
+        Ok(size)
+    }
+}
+
+/// The ncm file dump wrapper for the pushed data, such as the chunks of an upload.
+///
+/// The header is kept in memory until it's complete, and then the music data
+/// is decrypted and written to the inner writer.
+pub struct NcmWriter<W>
+where
+    W: Write,
+{
+    writer: W,
+    parser: ncm::Parser,
+    sections: NcmSections,
+    pending: Vec<u8>,
+}
+
+impl<W> NcmWriter<W>
+where
+    W: Write,
+{
+    /// Create a NcmWriter, the decrypted music data is written to the writer.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::fs::File;
+    /// use std::io::{Read, Write};
+    ///
+    /// use anyhow::Result;
+    /// use ncmdump::NcmWriter;
+    ///
+    /// fn main() -> Result<()> {
+    ///     let mut file = File::open("res/test.ncm")?;
+    ///     let mut data = Vec::new();
+    ///     file.read_to_end(&mut data)?;
+    ///
+    ///     let mut ncm = NcmWriter::new(Vec::new());
+    ///     for chunk in data.chunks(1024) {
+    ///         ncm.write_all(chunk)?;
+    ///     }
+    ///     let info = ncm.get_info()?;
+    ///     let music = ncm.finish()?;
+    ///     assert!(music.starts_with(b"fLaC"));
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            parser: ncm::Parser::new(),
+            sections: NcmSections::new(),
+            pending: Vec::new(),
+        }
+    }
+
+    /// Check if the header is parsed, the information and the images are known after it.
+    pub fn has_header(&self) -> bool {
+        self.parser.is_audio()
+    }
+
+    /// Get the layout of header, it's `None` if the length of image is not parsed yet.
+    pub fn get_header(&self) -> Option<&NcmHeader> {
+        self.parser.header()
+    }
+
+    /// Decode the information buffer and just return the information.
+    /// It returns `None` if the information is not parsed yet, or there is no information in the file.
+    pub fn get_info(&self) -> Result<Option<NcmInfo>> {
+        self.sections.get_info()
+    }
+
+    /// Get the image bytes, if it's exists and parsed.
+    pub fn get_image(&self) -> Option<&[u8]> {
+        self.sections.get_image()
+    }
+
+    /// Get every image in the cover frame, the cover is always the first one.
+    pub fn get_images(&self) -> Vec<NcmImage> {
+        self.sections.get_images()
+    }
+
+    /// Flush the music data and return the inner writer.
+    /// The error is returned if the data ends before the music data.
+    pub fn finish(mut self) -> Result<W> {
+        self.parser.finish()?;
+        self.flush()?;
+        Ok(self.writer)
+    }
+
+    /// Write the music data which is decrypted but not written yet.
+    fn write_pending(&mut self) -> std::io::Result<()> {
+        while !self.pending.is_empty() {
+            match self.writer.write(&self.pending) {
+                Ok(0) => return Err(std::io::ErrorKind::WriteZero.into()),
+                Ok(size) => {
+                    self.pending.drain(..size);
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<W> Write for NcmWriter<W>
+where
+    W: Write,
+{
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        // The consumed input can't be written again, so the error is only
+        // returned before the parser moves on
+        self.write_pending()?;
+        let mut consumed = 0;
+        loop {
+            let (size, event) = self
+                .parser
+                .parse(&buf[consumed..])
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+            consumed += size;
+            match event {
+                Event::NeedMoreData(_) => return Ok(consumed),
+                event => {
+                    if let Some(music) = self.sections.push(event) {
+                        // The music data is kept if it fails, and written by the next call
+                        self.pending = music;
+                        let _ = self.write_pending();
+                    }
+                    if consumed > 0 {
+                        return Ok(consumed);
+                    }
+                }
+            }
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.write_pending()?;
+        self.writer.flush()
+    }
+}
+
+/// The uc cache file dump wrapper, which is xored by a single byte.
+pub struct UcDump<S>
+where
+    S: Read,
+{
+    reader: S,
+    cursor: u64,
+}
+
+impl<S> UcDump<S>
+where
+    S: Read,
+{
+    fn encrypt(buffer: &mut [u8]) {
+        for byte in buffer.iter_mut() {
+            *byte ^= UC_KEY;
+        }
+    }
+
+    /// Create UcDump from reader.
+    /// The uc file has no header, so the content of reader can't be checked.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use std::fs::File;
+    /// #
+    /// # use ncmdump::UcDump;
+    /// #
+    /// let file = File::open("res/test.uc").expect("Can't open file");
+    /// let _ = UcDump::from_reader(file).unwrap();
+    /// ```
+    pub fn from_reader(reader: S) -> Result<Self> {
+        Ok(Self { reader, cursor: 0 })
+    }
+
+    /// Get the music data from ucdump.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use std::fs::File;
+    /// use std::io::Write;
+    ///
+    /// use anyhow::Result;
+    /// use ncmdump::UcDump;
+    ///
+    /// fn main() -> Result<()> {
+    ///     let file = File::open("res/test.uc")?;
+    ///     let mut uc = UcDump::from_reader(file)?;
+    ///     let music = uc.get_data()?;
+    ///
+    ///     let mut target = File::options()
+    ///         .create(true)
+    ///         .write(true)
+    ///         .open("res/test.flac")?;
+    ///     target.write_all(&music)?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn get_data(&mut self) -> std::io::Result<Vec<u8>> {
+        let mut data = Vec::new();
+        self.read_to_end(&mut data)?;
+        Ok(data)
+    }
+}
+
+impl<R> Read for UcDump<R>
+where
+    R: Read,
+{
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let size = self.reader.read(buf)?;
+        Self::encrypt(&mut buf[..size]);
+        self.cursor += size as u64;
+        Ok(size)
+    }
+}
+
+impl<R> Seek for UcDump<R>
+where
+    R: Read + Seek,
+{
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        self.cursor = self.reader.seek(pos)?;
         Ok(self.cursor)
     }
 }
 
+impl<R> Dump for UcDump<R> where R: Read + Seek {}
+
 #[cfg(test)]
 pub mod tests {
     use std::fs::File;
+    use std::io::Cursor;
 
     use anyhow::Result;
 
@@ -426,7 +757,7 @@ pub mod tests {
 
         assert_eq!(
             info,
-            NcmInfo {
+            Some(NcmInfo {
                 name: "寒鸦少年".to_string(),
                 id: 1305366556,
                 album: "寒鸦少年".to_string(),
@@ -436,149 +767,8 @@ pub mod tests {
                 format: "flac".to_string(),
                 mv_id: Some(0),
                 alias: Some(vec!["电视剧《斗破苍穹》主题曲".into()]),
-            },
-        );
-        Ok(())
-    }
-
-    #[test]
-    fn test_ncm_info_convert_ok() {
-        let info = NcmInfo::from(RawNcmInfo {
-            name: "".to_string(),
-            id: NcmId::String(String::from("")),
-            album: "".to_string(),
-            artist: vec![],
-            bitrate: NcmId::String(String::from("")),
-            duration: NcmId::String(String::from("")),
-            format: "".to_string(),
-            mv_id: None,
-            alias: None,
-        });
-        assert_eq!(info.id, 0);
-        assert_eq!(info.artist, Vec::new());
-        assert_eq!(info.bitrate, 0);
-        assert_eq!(info.duration, 0);
-    }
-
-    #[test]
-    fn test_ncm_info_deserialize_golden_hour_ok() -> Result<()> {
-        let raw = serde_json::from_slice::<RawNcmInfo>(
-            br#"{
-    "musicId": 1958557540,
-    "musicName": "golden hour",
-    "artist": [
-        [
-            "JVKE",
-            32988392
-        ]
-    ],
-    "albumId": 152231212,
-    "album": "this is what ____ feels like (Vol. 1-4)",
-    "albumPicDocId": "109951167909857256",
-    "albumPic": "https://p3.music.126.net/xKRQRZxEClE6653o7NbHiw==/109951167909857256.jpg",
-    "bitrate": 320000,
-    "mp3DocId": "d919d45cba30d6d5e5daff43b71cca8a",
-    "duration": 209259,
-    "mvId": 14570752,
-    "alias": [],
-    "transNames": [],
-    "format": "mp3",
-    "flag": 260
-}"#,
-        )?;
-        let info = NcmInfo::from(raw);
-        assert_eq!(info.id, 1958557540);
-        assert_eq!(info.artist, vec!((String::from("JVKE"), 32988392),));
-        assert_eq!(info.bitrate, 320000);
-        assert_eq!(info.duration, 209259);
-        assert_eq!(info.mv_id, Some(14570752));
-        Ok(())
-    }
-
-    #[test]
-    fn test_ncm_info_deserialize_endless_summer_ok() -> Result<()> {
-        let raw = serde_json::from_slice::<RawNcmInfo>(
-            br#"{
-    "musicId": "2062898402",
-    "musicName": "Endless Summer",
-    "artist": [
-        [
-            "Alan Walker",
-            "1045123"
-        ],
-        [
-            "Zak Abel",
-            "1053190"
-        ]
-    ],
-    "albumId": "178429151",
-    "album": "Walkerworld",
-    "albumPicDocId": "109951169035717968",
-    "albumPic": "http://p3.music.126.net/V4y-2EL08KAaHWLa5wYJFw==/109951169035717968.jpg",
-    "bitrate": 320000,
-    "mp3DocId": "d0e253a78e474075cced9a9f7a35498c",
-    "duration": 187040,
-    "mvId": "14648342",
-    "transNames": [],
-    "format": "mp3",
-    "fee": 8,
-    "volumeDelta": -10.2386,
-    "privilege": {
-        "flag": 1277956
-    }
-}"#,
-        )?;
-        let info = NcmInfo::from(raw);
-        assert_eq!(info.id, 2062898402);
-        assert_eq!(
-            info.artist,
-            vec!(
-                (String::from("Alan Walker"), 1045123),
-                (String::from("Zak Abel"), 1053190)
-            )
+            }),
         );
-        assert_eq!(info.bitrate, 320000);
-        assert_eq!(info.duration, 187040);
-        assert_eq!(info.mv_id, Some(14648342));
-        Ok(())
-    }
-
-    #[test]
-    fn test_ncm_info_deserialize_eternal_regret_ok() -> Result<()> {
-        let raw = serde_json::from_slice::<RawNcmInfo>(
-            br#"{
-   "musicId": "2056228066",
-   "musicName": "Eternal regret",
-   "artist": [
-      [
-         "AnRain",
-         "35516167"
-      ]
-   ],
-   "albumId": "167614757",
-   "album": "Eternal regret",
-   "albumPicDocId": "109951168681303606",
-   "albumPic": "http://p4.music.126.net/XfUBgbH9RDQqBcStBVa-Pw==/109951168681303606.jpg",
-   "bitrate": 320000,
-   "mp3DocId": "d27e546865e86910e1da2ccbefd00a15",
-   "duration": 186741,
-   "mvId": "",
-   "alias": [],
-   "transNames": [],
-   "format": "mp3",
-   "fee": 8,
-   "volumeDelta": -8.0989,
-   "privilege": {
-      "flag": 1544198
-   }
-}"#,
-        )?;
-        let info = NcmInfo::from(raw);
-        assert_eq!(info.id, 2056228066);
-        assert_eq!(info.artist, vec!((String::from("AnRain"), 35516167)));
-        assert_eq!(info.bitrate, 320000);
-        assert_eq!(info.duration, 186741);
-        assert_eq!(info.mv_id, None);
         Ok(())
     }
 
@@ -586,7 +776,7 @@ pub mod tests {
     fn test_get_image_ok() -> Result<()> {
         let reader = File::open("res/test.ncm")?;
         let mut ncm = NcmDump::from_reader(reader)?;
-        let image = ncm.get_image()?;
+        let image = ncm.get_image()?.unwrap_or_default();
         let length = image.len();
 
         assert_eq!(length, 39009);
@@ -608,6 +798,211 @@ pub mod tests {
         Ok(())
     }
 
+    /// Build a ncm file without the information and the cover from the test file.
+    fn build_bare_file() -> Result<Vec<u8>> {
+        let data = std::fs::read("res/test.ncm")?;
+        let header = NcmHeader::parse(&data)?;
+        let audio_start = header.audio_offset;
+
+        let mut file = data[..header.info.start - 4].to_vec();
+        file.extend_from_slice(&[0; 4]);
+        file.extend_from_slice(&[0; 5]);
+        file.extend_from_slice(&[0; 4]);
+        file.extend_from_slice(&[0; 4]);
+        file.extend_from_slice(&data[audio_start..]);
+        Ok(file)
+    }
+
+    /// Build a ncm file with the other image in the cover frame from the test file.
+    fn build_file_with_images(other: &[u8]) -> Result<Vec<u8>> {
+        let data = std::fs::read("res/test.ncm")?;
+        let NcmHeader { image, frame, .. } = NcmHeader::parse(&data)?;
+
+        let mut file = data[..image.start - 8].to_vec();
+        file.extend_from_slice(&((image.len() + other.len()) as u32).to_le_bytes());
+        file.extend_from_slice(&(image.len() as u32).to_le_bytes());
+        file.extend_from_slice(&data[image]);
+        file.extend_from_slice(other);
+        file.extend_from_slice(&data[frame.end..]);
+        Ok(file)
+    }
+
+    #[test]
+    fn test_get_images_ok() -> Result<()> {
+        let mut ncm = NcmDump::from_reader(File::open("res/test.ncm")?)?;
+        let images = ncm.get_images()?;
+        assert_eq!(images.len(), 1);
+        assert_eq!(images[0].kind, NcmImageKind::Cover);
+        assert_eq!(images[0].data.len(), 39009);
+
+        let other = [0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A];
+        let mut ncm = NcmDump::from_reader(Cursor::new(build_file_with_images(&other)?))?;
+        let images = ncm.get_images()?;
+        assert_eq!(images.len(), 2);
+        assert_eq!(images[0].kind, NcmImageKind::Cover);
+        assert_eq!(images[0].data.len(), 39009);
+        assert_eq!(images[1].kind, NcmImageKind::Other);
+        assert_eq!(images[1].data, other);
+
+        let data = ncm.get_data()?;
+        assert_eq!(data.len(), 61440);
+        assert_eq!(data[..4], [0x66, 0x4c, 0x61, 0x43]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_header_ok() -> Result<()> {
+        let data = std::fs::read("res/test.ncm")?;
+        let mut reader = Cursor::new(b"padding".to_vec());
+        reader.set_position(7);
+        reader.get_mut().extend_from_slice(&data);
+
+        // The offsets are from the start of ncm data, not the start of reader
+        let mut ncm = NcmDump::from_reader(reader)?;
+        assert_eq!(ncm.get_header(), &NcmHeader::parse(&data)?);
+        assert_eq!(ncm.get_data()?.len(), 61440);
+
+        let ncm = NcmDump::from_reader(Cursor::new(build_bare_file()?))?;
+        let header = ncm.get_header();
+        assert!(header.info.is_empty());
+        assert!(header.frame.is_empty());
+        assert_eq!(header.audio_offset, header.crc_gap.end + 8);
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_info_none_ok() -> Result<()> {
+        let mut ncm = NcmDump::from_reader(Cursor::new(build_bare_file()?))?;
+        assert_eq!(ncm.get_info()?, None);
+        assert_eq!(ncm.get_image()?, None);
+        assert_eq!(ncm.get_images()?, Vec::new());
+
+        let data = ncm.get_data()?;
+        assert_eq!(data.len(), 61440);
+        assert_eq!(data[..4], [0x66, 0x4c, 0x61, 0x43]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_ncm_stream_dump_ok() -> Result<()> {
+        let other = [0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A];
+        let file = build_file_with_images(&other)?;
+        let mut expect = NcmDump::from_reader(Cursor::new(&file))?;
+
+        // The chained reader returns the short read at the boundary
+        let (head, tail) = file.split_at(100);
+        let mut ncm = NcmStreamDump::from_reader(head.chain(tail))?;
+        assert_eq!(ncm.get_header(), expect.get_header());
+        assert_eq!(ncm.get_info()?, expect.get_info()?);
+        assert_eq!(ncm.get_image().map(<[u8]>::to_vec), expect.get_image()?);
+        assert_eq!(ncm.get_images(), expect.get_images()?);
+        assert_eq!(ncm.get_data()?, expect.get_data()?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_ncm_stream_dump_bare_ok() -> Result<()> {
+        let file = build_bare_file()?;
+        let mut ncm = NcmStreamDump::from_reader(&file[..])?;
+        assert_eq!(ncm.get_info()?, None);
+        assert_eq!(ncm.get_image(), None);
+        assert_eq!(ncm.get_images(), Vec::new());
+        assert_eq!(ncm.get_data()?.len(), 61440);
+        Ok(())
+    }
+
+    #[test]
+    fn test_ncm_stream_dump_err() -> Result<()> {
+        let file = std::fs::read("res/test.ncm")?;
+        let result = NcmStreamDump::from_reader(&file[..1000]);
+        assert!(matches!(result, Err(Errors::InvalidImageLength)));
+
+        let result = NcmStreamDump::from_reader(&file[..100]);
+        assert!(matches!(result, Err(Errors::InvalidKeyLength)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_ncm_writer_ok() -> Result<()> {
+        let data = std::fs::read("res/test.ncm")?;
+        let mut expect = NcmDump::from_reader(File::open("res/test.ncm")?)?;
+
+        let mut ncm = NcmWriter::new(Vec::new());
+        assert_eq!(ncm.get_header(), None);
+        for chunk in data.chunks(7) {
+            ncm.write_all(chunk)?;
+        }
+        assert!(ncm.has_header());
+        assert_eq!(ncm.get_header(), Some(expect.get_header()));
+        assert_eq!(ncm.get_info()?, expect.get_info()?);
+        assert_eq!(ncm.get_images(), expect.get_images()?);
+        assert_eq!(ncm.finish()?, expect.get_data()?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_ncm_writer_retry_ok() -> Result<()> {
+        /// The writer which fails at the first two writes.
+        struct FlakyWriter(Vec<u8>, usize);
+
+        impl Write for FlakyWriter {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                if self.1 < 2 {
+                    self.1 += 1;
+                    return Err(std::io::ErrorKind::Other.into());
+                }
+                self.0.write(buf)
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let data = std::fs::read("res/test.ncm")?;
+        let mut expect = NcmDump::from_reader(File::open("res/test.ncm")?)?;
+
+        let mut ncm = NcmWriter::new(FlakyWriter(Vec::new(), 0));
+        let header_length = expect.get_header().audio_offset;
+        // The first failure is kept with the music data, the second one is returned
+        ncm.write_all(&data[..header_length + 16])?;
+        assert!(ncm.write_all(&data[header_length + 16..]).is_err());
+        ncm.write_all(&data[header_length + 16..])?;
+        assert_eq!(ncm.finish()?.0, expect.get_data()?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_ncm_writer_bare_ok() -> Result<()> {
+        let mut ncm = NcmWriter::new(Vec::new());
+        ncm.write_all(&build_bare_file()?)?;
+        assert_eq!(ncm.get_info()?, None);
+        assert_eq!(ncm.get_image(), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_ncm_writer_err() -> Result<()> {
+        let mut ncm = NcmWriter::new(Vec::new());
+        let result = ncm.write_all(b"NOTANCMFILE");
+        assert!(result.is_err());
+
+        let data = std::fs::read("res/test.ncm")?;
+        let mut ncm = NcmWriter::new(Vec::new());
+        ncm.write_all(&data[..200])?;
+        assert!(!ncm.has_header());
+        assert!(matches!(ncm.finish(), Err(Errors::InvalidInfoLength)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_info_short_err() -> Result<()> {
+        let mut ncm = NcmDump::from_reader(File::open("res/test.ncm")?)?;
+        ncm.sections.info.truncate(10);
+        assert!(matches!(ncm.get_info(), Err(Errors::InfoDecodeError)));
+        Ok(())
+    }
+
     #[test]
     fn test_get_data_ok() -> Result<()> {
         let reader = File::open("res/test.ncm")?;
@@ -636,9 +1031,9 @@ pub mod tests {
     #[test]
     fn test_encrypt_ok() -> Result<()> {
         let reader = File::open("res/test.ncm")?;
-        let mut ncm = NcmDump::from_reader(reader)?;
+        let dump = NcmDump::from_reader(reader)?;
         let mut data = [63, 246, 41, 107];
-        ncm.encrypt(0, &mut data);
+        ncm::encrypt(&dump.header.key_box, 0, &mut data);
         assert_eq!(data, [102, 76, 97, 67]);
         Ok(())
     }
@@ -672,104 +1067,56 @@ pub mod tests {
     }
 
     #[test]
-    fn test_decrypt() {
-        let key = [
-            0x23, 0x31, 0x34, 0x6C, 0x6A, 0x6B, 0x5F, 0x21, 0x5C, 0x5D, 0x26, 0x30, 0x55, 0x3C,
-            0x27, 0x28,
-        ];
-        let source = [
-            0x2F, 0xAE, 0x05, 0x53, 0x75, 0xB8, 0x63, 0x15, 0xF7, 0x64, 0x54, 0xE6, 0x6D, 0x9F,
-            0xBC, 0xFD, 0xD4, 0xC3, 0xE5, 0x4A, 0x5D, 0x7D, 0x61, 0x47, 0x93, 0x82, 0x00, 0xAC,
-            0x48, 0xE4, 0x2C, 0x96, 0x43, 0x0F, 0x53, 0x54, 0xBE, 0x36, 0xC8, 0x6D, 0xE5, 0x46,
-            0x47, 0x7E, 0x87, 0x88, 0xBC, 0xF6, 0x0F, 0x97, 0x0F, 0xA9, 0x60, 0xDB, 0xF7, 0x4C,
-            0xD1, 0xD0, 0xD4, 0x44, 0xEC, 0xF6, 0x89, 0x44, 0x6B, 0x88, 0xD3, 0x2A, 0x39, 0x25,
-            0xB8, 0x09, 0x71, 0xA9, 0x3F, 0xE0, 0xC1, 0x07, 0x22, 0x3A, 0xB6, 0x0B, 0xF0, 0xA1,
-            0xB3, 0x26, 0x96, 0x62, 0xBD, 0x30, 0x76, 0xB5, 0xDA, 0x03, 0x6B, 0xB0, 0x99, 0x43,
-            0x2E, 0x7B, 0x8F, 0xD4, 0xD2, 0x9E, 0xFF, 0x37, 0x24, 0xB5, 0x25, 0x56, 0x6A, 0x0B,
-            0x2B, 0x41, 0x19, 0x52, 0xF6, 0xC8, 0x6E, 0x56, 0xE8, 0xC4, 0xF0, 0xCA, 0xFC, 0x9F,
-            0x09, 0xA4, 0xED, 0x51, 0x94, 0x7E, 0xDD, 0xE4, 0xF1, 0x3D, 0x63, 0xEB, 0x7A, 0xBB,
-            0x8C, 0xE6, 0x8F, 0x42, 0xDA, 0xE1, 0x7F, 0x17, 0xB5, 0x06, 0xC8, 0x34, 0x1C, 0xD7,
-            0x4C, 0x7F, 0xBE, 0x7B, 0xE8, 0x66, 0xB1, 0x0C, 0xB6, 0x57, 0x65, 0x78, 0xCF, 0xD0,
-            0xBA, 0xCC, 0x78, 0x09, 0xC1, 0x0F, 0x50, 0xDE, 0x1A, 0x2A, 0x27, 0x5D, 0x83, 0x12,
-            0xBB, 0x3A, 0xA5, 0x12, 0xFB, 0x54, 0xA2, 0xC9, 0x4D, 0x15, 0x46, 0x9F, 0x36, 0x8D,
-            0xF6, 0x79, 0x98, 0xA7, 0x34, 0x7F, 0x84, 0x6D, 0xD9, 0xC7, 0x3C, 0x51, 0xA6, 0x8B,
-            0x95, 0x42, 0x09, 0x13, 0xA9, 0xAE, 0x2C, 0xE4, 0x5F, 0x8A, 0x26, 0x67, 0x3B, 0xF5,
-            0x01, 0x36, 0x0D, 0x20, 0xD0, 0x2C, 0xDE, 0xA1, 0xE2, 0x03, 0x62, 0xD3, 0xB8, 0x9F,
-            0x65, 0xE8, 0xD7, 0xC1, 0x05, 0x88, 0x83, 0x68, 0x50, 0xDB, 0xC7, 0x0F, 0xE5, 0x79,
-            0xF7, 0x3F, 0x37, 0x4A, 0xF4, 0xD9, 0x82, 0xB1, 0xB3, 0x04, 0x0B, 0xB0, 0xD8, 0x5C,
-            0xFA, 0x03, 0x9C, 0x63, 0xFF, 0xCA, 0xE2, 0xE4, 0x57, 0x08, 0x2A, 0x05, 0x34, 0x16,
-            0x03, 0xED, 0xC7, 0x85, 0xE0, 0x3C, 0x43, 0x53, 0x78, 0x8C, 0x88, 0x7F, 0x52, 0xE6,
-            0x94, 0x5D, 0xC7, 0x9A, 0x21, 0xD0, 0xEA, 0x89, 0x7B, 0x09, 0xB0, 0xDA, 0xA2, 0x0B,
-            0xEE, 0xC2, 0x66, 0x04, 0x2A, 0xA0, 0x9C, 0x28, 0xDC, 0xA9, 0xB4, 0x71, 0x90, 0xC8,
-            0x9B, 0x00, 0x7B, 0xF5, 0x7A, 0x9C, 0xAF, 0x2B, 0x8A, 0x7B, 0x0F, 0x70, 0x7F, 0x44,
-            0x01, 0x5A, 0xDB, 0x6D, 0x8E, 0x98, 0x3D, 0x4E, 0x14, 0x71, 0xC1, 0xB2, 0x0F, 0x66,
-            0x8F, 0x14, 0x0A, 0x5C, 0x78, 0xE9, 0x16, 0xD2, 0x45, 0x4F, 0x1F, 0xE3, 0x3F, 0x0E,
-            0xBD, 0x5D, 0x40, 0x75,
-        ];
-        let target = [
-            0x6D, 0x75, 0x73, 0x69, 0x63, 0x3A, 0x7B, 0x22, 0x6D, 0x75, 0x73, 0x69, 0x63, 0x49,
-            0x64, 0x22, 0x3A, 0x31, 0x33, 0x30, 0x35, 0x33, 0x36, 0x36, 0x35, 0x35, 0x36, 0x2C,
-            0x22, 0x6D, 0x75, 0x73, 0x69, 0x63, 0x4E, 0x61, 0x6D, 0x65, 0x22, 0x3A, 0x22, 0xE5,
-            0xAF, 0x92, 0xE9, 0xB8, 0xA6, 0xE5, 0xB0, 0x91, 0xE5, 0xB9, 0xB4, 0x22, 0x2C, 0x22,
-            0x61, 0x6C, 0x69, 0x61, 0x73, 0x22, 0x3A, 0x5B, 0x22, 0xE7, 0x94, 0xB5, 0xE8, 0xA7,
-            0x86, 0xE5, 0x89, 0xA7, 0xE3, 0x80, 0x8A, 0xE6, 0x96, 0x97, 0xE7, 0xA0, 0xB4, 0xE8,
-            0x8B, 0x8D, 0xE7, 0xA9, 0xB9, 0xE3, 0x80, 0x8B, 0xE4, 0xB8, 0xBB, 0xE9, 0xA2, 0x98,
-            0xE6, 0x9B, 0xB2, 0x22, 0x5D, 0x2C, 0x22, 0x61, 0x72, 0x74, 0x69, 0x73, 0x74, 0x22,
-            0x3A, 0x5B, 0x5B, 0x22, 0xE5, 0x8D, 0x8E, 0xE6, 0x99, 0xA8, 0xE5, 0xAE, 0x87, 0x22,
-            0x2C, 0x38, 0x36, 0x31, 0x37, 0x37, 0x37, 0x5D, 0x5D, 0x2C, 0x22, 0x61, 0x6C, 0x62,
-            0x75, 0x6D, 0x49, 0x64, 0x22, 0x3A, 0x37, 0x32, 0x37, 0x30, 0x36, 0x37, 0x38, 0x38,
-            0x2C, 0x22, 0x61, 0x6C, 0x62, 0x75, 0x6D, 0x22, 0x3A, 0x22, 0xE5, 0xAF, 0x92, 0xE9,
-            0xB8, 0xA6, 0xE5, 0xB0, 0x91, 0xE5, 0xB9, 0xB4, 0x22, 0x2C, 0x22, 0x61, 0x6C, 0x62,
-            0x75, 0x6D, 0x50, 0x69, 0x63, 0x44, 0x6F, 0x63, 0x49, 0x64, 0x22, 0x3A, 0x31, 0x30,
-            0x39, 0x39, 0x35, 0x31, 0x31, 0x36, 0x33, 0x35, 0x32, 0x30, 0x36, 0x33, 0x38, 0x35,
-            0x32, 0x32, 0x2C, 0x22, 0x61, 0x6C, 0x62, 0x75, 0x6D, 0x50, 0x69, 0x63, 0x22, 0x3A,
-            0x22, 0x68, 0x74, 0x74, 0x70, 0x3A, 0x2F, 0x2F, 0x70, 0x33, 0x2E, 0x6D, 0x75, 0x73,
-            0x69, 0x63, 0x2E, 0x31, 0x32, 0x36, 0x2E, 0x6E, 0x65, 0x74, 0x2F, 0x71, 0x52, 0x51,
-            0x54, 0x53, 0x5F, 0x54, 0x72, 0x6F, 0x5A, 0x6F, 0x39, 0x53, 0x4C, 0x56, 0x35, 0x79,
-            0x71, 0x70, 0x54, 0x35, 0x41, 0x3D, 0x3D, 0x2F, 0x31, 0x30, 0x39, 0x39, 0x35, 0x31,
-            0x31, 0x36, 0x33, 0x35, 0x32, 0x30, 0x36, 0x33, 0x38, 0x35, 0x32, 0x32, 0x2E, 0x6A,
-            0x70, 0x67, 0x22, 0x2C, 0x22, 0x6D, 0x76, 0x49, 0x64, 0x22, 0x3A, 0x30, 0x2C, 0x22,
-            0x62, 0x69, 0x74, 0x72, 0x61, 0x74, 0x65, 0x22, 0x3A, 0x39, 0x32, 0x33, 0x33, 0x37,
-            0x38, 0x2C, 0x22, 0x64, 0x75, 0x72, 0x61, 0x74, 0x69, 0x6F, 0x6E, 0x22, 0x3A, 0x33,
-            0x31, 0x35, 0x31, 0x34, 0x36, 0x2C, 0x22, 0x66, 0x6F, 0x72, 0x6D, 0x61, 0x74, 0x22,
-            0x3A, 0x22, 0x66, 0x6C, 0x61, 0x63, 0x22, 0x7D,
-        ];
-        let result = NcmDump::<File>::decrypt(&source, &key).unwrap();
-        assert_eq!(&result[..], &target);
+    fn test_ncmdump_seek_ok() -> Result<()> {
+        let reader = File::open("res/test.ncm")?;
+        let mut ncm = NcmDump::from_reader(reader)?;
+        let mut buf = [0; 4];
+
+        assert_eq!(ncm.seek(SeekFrom::Start(4))?, 4);
+        ncm.read_exact(&mut buf)?;
+        assert_eq!(buf, [0x00, 0x00, 0x00, 0x22]);
+        assert!(ncm.seek(SeekFrom::Current(-16)).is_err());
+        assert!(ncm.seek(SeekFrom::Start(u64::MAX)).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_ucdump_get_data_ok() -> Result<()> {
+        let expect = std::fs::read("res/test.uc")?
+            .iter()
+            .map(|byte| byte ^ UC_KEY)
+            .collect::<Vec<u8>>();
+        assert!(expect.starts_with(b"fLaC"));
+
+        let mut uc = UcDump::from_reader(File::open("res/test.uc")?)?;
+        assert_eq!(uc.get_data()?, expect);
+
+        let mut buf = [0; 4];
+        uc.seek(SeekFrom::Start(4))?;
+        uc.read_exact(&mut buf)?;
+        assert_eq!(buf, expect[4..8]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_ncm_info_from_index_ok() -> Result<()> {
+        let info = NcmInfo::from_index(File::open("res/test.idx")?)?;
+        assert_eq!(info.id, 1305366556);
+        assert_eq!(info.name, "寒鸦少年");
+        assert_eq!(info.artist, vec![(String::from("华晨宇"), 861777)]);
+        assert_eq!(info.bitrate, 923378);
+        assert_eq!(info.format, "flac");
+
+        let info = NcmInfo::from_index(&br#"{"musicId":"1958557540","size":4096}"#[..])?;
+        assert_eq!(info.id, 1958557540);
+        assert_eq!(info.name, "");
+        assert_eq!(info.artist, Vec::new());
+        Ok(())
     }
 
     #[test]
-    fn test_build_key_box() {
-        let key = [
-            0x31, 0x31, 0x38, 0x31, 0x39, 0x38, 0x30, 0x33, 0x33, 0x32, 0x38, 0x35, 0x45, 0x37,
-            0x66, 0x54, 0x34, 0x39, 0x78, 0x37, 0x64, 0x6F, 0x66, 0x39, 0x4F, 0x4B, 0x43, 0x67,
-            0x67, 0x39, 0x63, 0x64, 0x76, 0x68, 0x45, 0x75, 0x65, 0x7A, 0x79, 0x33, 0x69, 0x5A,
-            0x43, 0x4C, 0x31, 0x6E, 0x46, 0x76, 0x42, 0x46, 0x64, 0x31, 0x54, 0x34, 0x75, 0x53,
-            0x6B, 0x74, 0x41, 0x4A, 0x4B, 0x6D, 0x77, 0x5A, 0x58, 0x73, 0x69, 0x6A, 0x50, 0x62,
-            0x69, 0x6A, 0x6C, 0x69, 0x69, 0x6F, 0x6E, 0x56, 0x55, 0x58, 0x58, 0x67, 0x39, 0x70,
-            0x6C, 0x54, 0x62, 0x58, 0x45, 0x63, 0x6C, 0x41, 0x45, 0x39, 0x4C, 0x62,
-        ];
-        let key_box = [
-            0x43, 0x63, 0x9D, 0xE2, 0x5B, 0x4B, 0x55, 0xBB, 0x4C, 0xCF, 0x2A, 0x62, 0x0E, 0x48,
-            0x8A, 0x15, 0x59, 0x52, 0xBA, 0x6C, 0xEF, 0x6D, 0x72, 0x39, 0xA0, 0x9A, 0xA9, 0x27,
-            0x66, 0xBC, 0xF9, 0xC0, 0x47, 0xDF, 0x7D, 0xDE, 0x3B, 0x81, 0x04, 0xFF, 0x90, 0x77,
-            0x80, 0x50, 0x54, 0xBD, 0x0D, 0x58, 0x34, 0x0A, 0x44, 0xA8, 0x5F, 0x99, 0xC6, 0xBE,
-            0x4E, 0x4D, 0x13, 0x17, 0x83, 0x01, 0x35, 0x5C, 0xF4, 0x7B, 0x53, 0x31, 0x86, 0xD4,
-            0xB8, 0xAB, 0xD1, 0xB5, 0x68, 0xDC, 0x96, 0xF1, 0x9C, 0xE8, 0x7A, 0x1B, 0xB0, 0x56,
-            0x22, 0x1A, 0x51, 0x92, 0xBF, 0xFA, 0xB1, 0x19, 0x88, 0x26, 0x49, 0x08, 0xEB, 0xAC,
-            0x14, 0x28, 0xAD, 0x3A, 0x8C, 0x85, 0x84, 0x2C, 0x82, 0xB3, 0xA6, 0xA2, 0xA3, 0x12,
-            0x78, 0xA1, 0x57, 0xAE, 0x00, 0x2F, 0xB6, 0x61, 0xA5, 0x6F, 0x5A, 0x89, 0x29, 0x46,
-            0x2E, 0x4F, 0x36, 0x40, 0x07, 0x87, 0xA7, 0x65, 0x73, 0xC4, 0x7C, 0x33, 0x1E, 0xE5,
-            0x10, 0xB4, 0xFD, 0xC9, 0xE0, 0xB7, 0x97, 0x32, 0x5D, 0x64, 0x41, 0xF0, 0x20, 0xC3,
-            0x95, 0xFE, 0xD2, 0x21, 0xFB, 0x75, 0x3D, 0x0B, 0x3E, 0xF2, 0xD5, 0xCB, 0xD6, 0xF7,
-            0x1F, 0x24, 0x45, 0x69, 0xB9, 0xDA, 0x6A, 0x76, 0x03, 0xF8, 0x70, 0x8E, 0xC1, 0xC8,
-            0xD7, 0x4A, 0xD0, 0x9E, 0xCD, 0xA4, 0xCE, 0xAA, 0x1D, 0xED, 0xF6, 0x02, 0x60, 0xE3,
-            0xDB, 0x8D, 0x09, 0xF3, 0x37, 0xE1, 0xC5, 0xCA, 0x8F, 0x2D, 0x7F, 0x74, 0x42, 0x6E,
-            0x8B, 0x3F, 0x23, 0xC2, 0xD3, 0xCC, 0xD9, 0xEE, 0x98, 0xE6, 0x11, 0x05, 0xEA, 0xD8,
-            0xB2, 0xE4, 0xF5, 0xE7, 0x71, 0x2B, 0x93, 0x9B, 0x3C, 0x30, 0xE9, 0xC7, 0x38, 0xEC,
-            0x18, 0x6B, 0x79, 0xFC, 0xAF, 0x5E, 0x9F, 0x7E, 0x91, 0xDD, 0x16, 0x94, 0x0F, 0x06,
-            0x67, 0x25, 0x0C, 0x1C,
-        ];
-        assert_eq!(NcmDump::<File>::build_key_box(&key), key_box);
+    fn test_ncm_info_from_index_err() {
+        let result = NcmInfo::from_index(&b"{}"[..]);
+        assert!(matches!(result, Err(Errors::InfoDecodeError)));
     }
 }
@@ -0,0 +1,166 @@
+use std::fs::File;
+use std::io::{Read, Seek};
+use std::path::Path;
+
+use crate::dump::Dump;
+use crate::error::{Errors, Result};
+use crate::utils::FileType;
+
+/// Open the reader with the dumper detected by the magic header.
+///
+/// The file without magic header, like the qmc v2 file, can't be detected
+/// by this function, please use [`open_path`] or [`open_as`] instead.
+///
+/// # Example
+///
+/// ```rust
+/// use std::fs::File;
+/// use std::io::Read;
+///
+/// use anyhow::Result;
+///
+/// fn main() -> Result<()> {
+///     let file = File::open("res/test.ncm")?;
+///     let mut dump = ncmdump::open(file)?;
+///     let mut music = Vec::new();
+///     dump.read_to_end(&mut music)?;
+///     Ok(())
+/// }
+/// ```
+pub fn open<R>(mut reader: R) -> Result<Box<dyn Dump>>
+where
+    R: Read + Seek + 'static,
+{
+    let file_type = FileType::detect(&mut reader, None)?;
+    open_as(reader, file_type)
+}
+
+/// Open the file with the dumper detected by the magic header and the extension.
+///
+/// # Example
+///
+/// ```rust
+/// use std::io::Read;
+///
+/// use anyhow::Result;
+///
+/// fn main() -> Result<()> {
+///     let mut dump = ncmdump::open_path("res/test.mflac")?;
+///     let mut music = Vec::new();
+///     dump.read_to_end(&mut music)?;
+///     Ok(())
+/// }
+/// ```
+pub fn open_path<P>(path: P) -> Result<Box<dyn Dump>>
+where
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+    let mut file = File::open(path)?;
+    let extension = path.extension().and_then(|ext| ext.to_str());
+    let file_type = FileType::detect(&mut file, extension)?;
+    open_as(file, file_type)
+}
+
+/// Open the reader with the dumper of specified file type.
+///
+/// The kgg and joox file need the key database or the uuid of device,
+/// they should be created by `KggDump` and `JooxDump` directly.
+///
+/// # Example
+///
+/// ```rust
+/// # use std::fs::File;
+/// #
+/// # use ncmdump::utils::FileType;
+/// #
+/// let file = File::open("res/test.qmcflac").expect("Can't open file");
+/// let _ = ncmdump::open_as(file, FileType::Qmc).unwrap();
+/// ```
+// The reader is unused if none of the enabled dumpers reads it
+#[cfg_attr(
+    not(any(
+        feature = "ncmdump",
+        feature = "qmcdump",
+        feature = "kgmdump",
+        feature = "kwmdump",
+        feature = "xmdump",
+        feature = "xmlydump"
+    )),
+    allow(unused_variables)
+)]
+pub fn open_as<R>(reader: R, file_type: FileType) -> Result<Box<dyn Dump>>
+where
+    R: Read + Seek + 'static,
+{
+    match file_type {
+        #[cfg(feature = "ncmdump")]
+        FileType::Ncm => Ok(Box::new(crate::NcmDump::from_reader(reader)?)),
+        #[cfg(feature = "ncmdump")]
+        FileType::Uc => Ok(Box::new(crate::UcDump::from_reader(reader)?)),
+        #[cfg(feature = "qmcdump")]
+        FileType::Qmc => Ok(Box::new(crate::QmcDump::from_seekable_reader(reader)?)),
+        #[cfg(feature = "qmcdump")]
+        FileType::QmcV2 => Ok(Box::new(crate::QmcV2Dump::from_reader(reader)?)),
+        #[cfg(feature = "qmcdump")]
+        FileType::Tm => Ok(Box::new(crate::TmDump::from_reader(reader)?)),
+        #[cfg(feature = "kgmdump")]
+        FileType::Kgm => Ok(Box::new(crate::KgmDump::from_reader(reader)?)),
+        #[cfg(feature = "kggdump")]
+        FileType::Kgg => Err(Errors::KeyNotFound),
+        #[cfg(feature = "kwmdump")]
+        FileType::Kwm => Ok(Box::new(crate::KwmDump::from_reader(reader)?)),
+        #[cfg(feature = "xmdump")]
+        FileType::Xm => Ok(Box::new(crate::XmDump::from_reader(reader)?)),
+        #[cfg(feature = "xmlydump")]
+        FileType::X2m => Ok(Box::new(crate::XmlyDump::from_reader(
+            reader,
+            crate::XmlyKind::X2m,
+        )?)),
+        #[cfg(feature = "xmlydump")]
+        FileType::X3m => Ok(Box::new(crate::XmlyDump::from_reader(
+            reader,
+            crate::XmlyKind::X3m,
+        )?)),
+        #[cfg(feature = "jooxdump")]
+        FileType::Joox => Err(Errors::KeyNotFound),
+        FileType::Other => Err(Errors::InvalidFileType),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use anyhow::Result;
+
+    use super::*;
+
+    #[cfg(feature = "ncmdump")]
+    #[test]
+    fn test_open_ok() -> Result<()> {
+        let mut expect = crate::NcmDump::from_reader(File::open("res/test.ncm")?)?;
+        let mut dump = open(File::open("res/test.ncm")?)?;
+        let mut data = Vec::new();
+        dump.read_to_end(&mut data)?;
+        assert_eq!(data, expect.get_data()?);
+        assert!(dump.get_info()?.is_some());
+        Ok(())
+    }
+
+    #[cfg(feature = "xmlydump")]
+    #[test]
+    fn test_open_path_ok() -> Result<()> {
+        let mut dump = open_path("res/test.x3m")?;
+        let mut buf = [0; 4];
+        dump.read_exact(&mut buf)?;
+        assert_eq!(&buf, b"fLaC");
+        Ok(())
+    }
+
+    #[test]
+    fn test_open_err() {
+        let result = open(Cursor::new([0; 16]));
+        assert!(matches!(result, Err(Errors::InvalidFileType)));
+    }
+}
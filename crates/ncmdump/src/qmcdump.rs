@@ -1,26 +1,18 @@
 use std::io::{Read, Seek, SeekFrom, Write};
 
-use crate::error::Result;
+use crate::audio::AudioFormat;
+use crate::dump::Dump;
+use crate::ekey;
+use crate::error::{Errors, Result};
+pub use crate::qmcdump::trailer::{QmcTrailer, QmcTrailerKind};
+use crate::raw::qmc;
+
+mod trailer;
 
 const BUFFER_SIZE: usize = 8192;
-const KEY: [u8; 256] = [
-    0x77, 0x48, 0x32, 0x73, 0xDE, 0xF2, 0xC0, 0xC8, 0x95, 0xEC, 0x30, 0xB2, 0x51, 0xC3, 0xE1, 0xA0,
-    0x9E, 0xE6, 0x9D, 0xCF, 0xFA, 0x7F, 0x14, 0xD1, 0xCE, 0xB8, 0xDC, 0xC3, 0x4A, 0x67, 0x93, 0xD6,
-    0x28, 0xC2, 0x91, 0x70, 0xCA, 0x8D, 0xA2, 0xA4, 0xF0, 0x08, 0x61, 0x90, 0x7E, 0x6F, 0xA2, 0xE0,
-    0xEB, 0xAE, 0x3E, 0xB6, 0x67, 0xC7, 0x92, 0xF4, 0x91, 0xB5, 0xF6, 0x6C, 0x5E, 0x84, 0x40, 0xF7,
-    0xF3, 0x1B, 0x02, 0x7F, 0xD5, 0xAB, 0x41, 0x89, 0x28, 0xF4, 0x25, 0xCC, 0x52, 0x11, 0xAD, 0x43,
-    0x68, 0xA6, 0x41, 0x8B, 0x84, 0xB5, 0xFF, 0x2C, 0x92, 0x4A, 0x26, 0xD8, 0x47, 0x6A, 0x7C, 0x95,
-    0x61, 0xCC, 0xE6, 0xCB, 0xBB, 0x3F, 0x47, 0x58, 0x89, 0x75, 0xC3, 0x75, 0xA1, 0xD9, 0xAF, 0xCC,
-    0x08, 0x73, 0x17, 0xDC, 0xAA, 0x9A, 0xA2, 0x16, 0x41, 0xD8, 0xA2, 0x06, 0xC6, 0x8B, 0xFC, 0x66,
-    0x34, 0x9F, 0xCF, 0x18, 0x23, 0xA0, 0x0A, 0x74, 0xE7, 0x2B, 0x27, 0x70, 0x92, 0xE9, 0xAF, 0x37,
-    0xE6, 0x8C, 0xA7, 0xBC, 0x62, 0x65, 0x9C, 0xC2, 0x08, 0xC9, 0x88, 0xB3, 0xF3, 0x43, 0xAC, 0x74,
-    0x2C, 0x0F, 0xD4, 0xAF, 0xA1, 0xC3, 0x01, 0x64, 0x95, 0x4E, 0x48, 0x9F, 0xF4, 0x35, 0x78, 0x95,
-    0x7A, 0x39, 0xD6, 0x6A, 0xA0, 0x6D, 0x40, 0xE8, 0x4F, 0xA8, 0xEF, 0x11, 0x1D, 0xF3, 0x1B, 0x3F,
-    0x3F, 0x07, 0xDD, 0x6F, 0x5B, 0x19, 0x30, 0x19, 0xFB, 0xEF, 0x0E, 0x37, 0xF0, 0x0E, 0xCD, 0x16,
-    0x49, 0xFE, 0x53, 0x47, 0x13, 0x1A, 0xBD, 0xA4, 0xF1, 0x40, 0x19, 0x60, 0x0E, 0xED, 0x68, 0x09,
-    0x06, 0x5F, 0x4D, 0xCF, 0x3D, 0x1A, 0xFE, 0x20, 0x77, 0xE4, 0xD9, 0xDA, 0xF9, 0xA4, 0x2B, 0x76,
-    0x1C, 0x71, 0xDB, 0x00, 0xBC, 0xFD, 0x0C, 0x6C, 0xA5, 0x47, 0xF7, 0xF6, 0x00, 0x79, 0x4A, 0x11,
-];
+const TM_HEADER_SIZE: usize = 8;
+const TM_MAGIC: &[u8; 4] = b"QQMU";
+const M4A_HEADER: [u8; TM_HEADER_SIZE] = [0x00, 0x00, 0x00, 0x20, 0x66, 0x74, 0x79, 0x70];
 
 /// The qmc file dump wrapper.
 pub struct QmcDump<S>
@@ -28,30 +20,16 @@ where
     S: Read,
 {
     reader: S,
-    cursor: u64,
+    parser: qmc::Parser,
 }
 
 impl<S> QmcDump<S>
 where
     S: Read,
 {
-    fn map_l(value: u64) -> u8 {
-        let v = if value > 0x7FFF {
-            value % 0x7FFF
-        } else {
-            value
-        } as usize;
-        let index = (v * v + 80923) % 256;
-        KEY[index]
-    }
-
-    fn encrypt(offset: u64, buffer: &mut [u8]) {
-        for (index, byte) in buffer.iter_mut().enumerate() {
-            *byte ^= Self::map_l(offset + index as u64);
-        }
-    }
-
     /// Create QmcDump from reader.
+    /// The whole reader is decrypted as the music data, including the trailer if any,
+    /// use [`QmcDump::from_seekable_reader`] to exclude it.
     ///
     /// # Example
     ///
@@ -64,7 +42,10 @@ where
     /// let _ = QmcDump::from_reader(file).unwrap();
     /// ```
     pub fn from_reader(reader: S) -> Result<Self> {
-        Ok(Self { reader, cursor: 0 })
+        Ok(Self {
+            reader,
+            parser: qmc::Parser::new(),
+        })
     }
 
     /// Get the music data from qmcdump.
@@ -81,7 +62,7 @@ where
     ///
     /// fn main() -> Result<()> {
     ///     let file = File::open("res/test.qmcflac")?;
-    ///     let mut qmc = QmcDump::from_reader(file)?;
+    ///     let mut qmc = QmcDump::from_seekable_reader(file)?;
     ///     let music = qmc.get_data()?;
     ///
     ///     let mut target = File::options()
@@ -95,7 +76,8 @@ where
     pub fn get_data(&mut self) -> std::io::Result<Vec<u8>> {
         let mut buffer = [0; BUFFER_SIZE];
         let mut output = Vec::new();
-        while let Ok(size) = self.read(&mut buffer) {
+        loop {
+            let size = self.read(&mut buffer)?;
             if size == 0 {
                 break;
             }
@@ -105,15 +87,70 @@ where
     }
 }
 
+impl<S> QmcDump<S>
+where
+    S: Read + Seek,
+{
+    /// Create QmcDump from a seekable reader.
+    /// The trailer at the end of reader is parsed and excluded from the music data.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use std::fs::File;
+    /// #
+    /// # use ncmdump::QmcDump;
+    /// #
+    /// let file = File::open("res/test.qmcflac").expect("Can't open file");
+    /// let _ = QmcDump::from_seekable_reader(file).unwrap();
+    /// ```
+    pub fn from_seekable_reader(mut reader: S) -> Result<Self> {
+        let trailer = QmcTrailer::parse(&mut reader)?;
+        reader.seek(SeekFrom::Start(0))?;
+        Ok(Self {
+            reader,
+            parser: qmc::Parser::new().with_length(trailer.length),
+        })
+    }
+}
+
+/// Read the music data by the parser, the trailer is never read from the reader.
+fn read_audio<R>(reader: &mut R, parser: &mut qmc::Parser, buf: &mut [u8]) -> std::io::Result<usize>
+where
+    R: Read,
+{
+    let length = match parser.remaining() {
+        Some(remain) => buf.len().min(usize::try_from(remain).unwrap_or(usize::MAX)),
+        None => buf.len(),
+    };
+    let size = reader.read(&mut buf[..length])?;
+    Ok(parser.decrypt(&mut buf[..size]))
+}
+
+/// Seek the reader, the end is the end of music data if the length is known.
+fn seek_audio<R>(reader: &mut R, parser: &mut qmc::Parser, pos: SeekFrom) -> std::io::Result<u64>
+where
+    R: Seek,
+{
+    let pos = match (pos, parser.length()) {
+        (SeekFrom::End(p), Some(length)) => {
+            SeekFrom::Start(length.checked_add_signed(p).ok_or_else(|| {
+                std::io::Error::new(std::io::ErrorKind::InvalidInput, "invalid seek")
+            })?)
+        }
+        _ => pos,
+    };
+    let position = reader.seek(pos)?;
+    parser.set_position(position);
+    Ok(position)
+}
+
 impl<R> Read for QmcDump<R>
 where
     R: Read,
 {
     fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
-        let size = self.reader.read(buf)?;
-        Self::encrypt(self.cursor, buf);
-        self.cursor += size as u64;
-        Ok(size)
+        read_audio(&mut self.reader, &mut self.parser, buf)
     }
 }
 
@@ -122,54 +159,264 @@ where
     R: Read + Seek,
 {
     fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
-        self.cursor = self.reader.seek(pos)?;
-        Ok(self.cursor)
+        seek_audio(&mut self.reader, &mut self.parser, pos)
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use std::fs::File;
-    use std::io::Cursor;
+impl<R> Dump for QmcDump<R> where R: Read + Seek {}
 
-    use anyhow::Result;
+/// The qmc v2 file dump wrapper, such as `.mflac` and `.mgg` file.
+pub struct QmcV2Dump<S>
+where
+    S: Read,
+{
+    reader: S,
+    parser: qmc::Parser,
+}
 
-    use super::*;
+impl<S> QmcV2Dump<S>
+where
+    S: Read + Seek,
+{
+    /// Create QmcV2Dump from a seekable reader.
+    /// The key of file is read from the end of reader.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use std::fs::File;
+    /// #
+    /// # use ncmdump::QmcV2Dump;
+    /// #
+    /// let file = File::open("res/test.mflac").expect("Can't open file");
+    /// let _ = QmcV2Dump::from_reader(file).unwrap();
+    /// ```
+    pub fn from_reader(mut reader: S) -> Result<Self> {
+        let trailer = QmcTrailer::parse(&mut reader)?;
+        let ekey = trailer.ekey.as_deref().ok_or(Errors::KeyNotFound)?;
+        let key = ekey::decrypt(ekey)?;
+        Self::from_trailer(reader, &trailer, &key)
+    }
 
-    #[test]
-    fn test_qmcdump_map_ok() {
-        let dest = QmcDump::<File>::map_l(0x99);
-        assert_eq!(dest, 146);
+    /// Create QmcV2Dump from a seekable reader and the decrypted key.
+    /// It's useful for the file which has no key in it,
+    /// the key can be got from the database of client and decrypted by [`ekey::decrypt`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use std::fs::File;
+    /// #
+    /// # use ncmdump::{ekey, QmcV2Dump};
+    /// #
+    /// let file = File::open("res/test.mflac").expect("Can't open file");
+    /// let key = ekey::decrypt(b"VGVzdEtleUbj/fFgGTIpxEXpSJ9uJiJLj5ZZm7TgLk+1bMB+Sqxb9Q==").unwrap();
+    /// let _ = QmcV2Dump::from_reader_with_key(file, &key).unwrap();
+    /// ```
+    pub fn from_reader_with_key(mut reader: S, key: &[u8]) -> Result<Self> {
+        let trailer = QmcTrailer::parse(&mut reader)?;
+        Self::from_trailer(reader, &trailer, key)
+    }
 
-        let dest = QmcDump::<File>::map_l(0x8FFF);
-        assert_eq!(dest, 195);
+    /// Create QmcV2Dump with the trailer which is parsed from the reader.
+    fn from_trailer(mut reader: S, trailer: &QmcTrailer, key: &[u8]) -> Result<Self> {
+        let parser = qmc::Parser::with_key(key)?.with_length(trailer.length);
+
+        reader.seek(SeekFrom::Start(0))?;
+        Ok(Self { reader, parser })
     }
 
-    #[test]
-    fn test_qmcdump_encrypt_ok() {
-        let mut data = [0x00, 0x01, 0x02, 0x03];
-        QmcDump::<File>::encrypt(0, &mut data);
-        assert_eq!(data, [0xC3, 0x4B, 0xD4, 0xC9]);
+    /// Get the music data from qmc v2 dump.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use std::fs::File;
+    /// use std::io::Write;
+    ///
+    /// use anyhow::Result;
+    /// use ncmdump::QmcV2Dump;
+    ///
+    /// fn main() -> Result<()> {
+    ///     let file = File::open("res/test.mflac")?;
+    ///     let mut qmc = QmcV2Dump::from_reader(file)?;
+    ///     let music = qmc.get_data()?;
+    ///
+    ///     let mut target = File::options()
+    ///         .create(true)
+    ///         .write(true)
+    ///         .open("res/test.flac")?;
+    ///     target.write_all(&music)?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn get_data(&mut self) -> Result<Vec<u8>> {
+        let mut data = Vec::new();
+        let mut buffer = [0; BUFFER_SIZE];
+        loop {
+            let size = self.read(&mut buffer)?;
+            if size == 0 {
+                break;
+            }
+            data.write_all(&buffer[..size])?;
+        }
+        Ok(data)
+    }
+}
 
-        let mut data = [0x00, 0x01, 0x02, 0x03];
-        QmcDump::<File>::encrypt(0x7fff, &mut data);
-        assert_eq!(data, [0x4A, 0x4B, 0xD4, 0xC9]);
+impl<R> Read for QmcV2Dump<R>
+where
+    R: Read + Seek,
+{
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        read_audio(&mut self.reader, &mut self.parser, buf)
     }
+}
 
-    #[test]
-    fn test_encrypt_head_ok() -> Result<()> {
-        // fLaC
-        let mut input = [0xA5, 0x06, 0xB7, 0x89];
-        QmcDump::<File>::encrypt(0, &mut input);
-        assert_eq!(input, [0x66, 0x4C, 0x61, 0x43]);
-
-        // ID3
-        let mut input = [0x8A, 0x0E, 0xE5];
-        QmcDump::<File>::encrypt(0, &mut input);
-        assert_eq!(input, [0x49, 0x44, 0x33]);
-        Ok(())
+impl<R> Seek for QmcV2Dump<R>
+where
+    R: Read + Seek,
+{
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        seek_audio(&mut self.reader, &mut self.parser, pos)
+    }
+}
+
+impl<R> Dump for QmcV2Dump<R> where R: Read + Seek {}
+
+/// The qq music cache file dump wrapper, such as `.tm0`, `.tm2`, `.tm3` and `.tm6` file.
+///
+/// The `.tm2` and `.tm6` file are m4a files with a mangled header,
+/// and the `.tm0` and `.tm3` file are plain mp3 files.
+pub struct TmDump<S>
+where
+    S: Read,
+{
+    reader: S,
+    cursor: u64,
+    header: [u8; TM_HEADER_SIZE],
+    format: &'static str,
+}
+
+impl<S> TmDump<S>
+where
+    S: Read,
+{
+    /// Create TmDump from reader.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use std::io::Cursor;
+    /// #
+    /// # use ncmdump::TmDump;
+    /// #
+    /// let data = b"QQMU\x00\x00\x00\x00\x6D\x34\x61\x20";
+    /// let tm = TmDump::from_reader(Cursor::new(data)).unwrap();
+    /// assert_eq!(tm.get_format(), "m4a");
+    /// ```
+    pub fn from_reader(mut reader: S) -> Result<Self> {
+        let mut header = [0; TM_HEADER_SIZE];
+        reader
+            .read_exact(&mut header)
+            .map_err(|_| Errors::InvalidFileType)?;
+        let format = if header.starts_with(TM_MAGIC) {
+            header = M4A_HEADER;
+            "m4a"
+        } else {
+            "mp3"
+        };
+        Ok(Self {
+            reader,
+            cursor: 0,
+            header,
+            format,
+        })
+    }
+
+    /// Get the format of music, it's `m4a` for the mangled header, otherwise `mp3`.
+    pub fn get_format(&self) -> &'static str {
+        self.format
     }
 
+    /// Get the music data from tmdump.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// # use std::io::Cursor;
+    /// #
+    /// # use ncmdump::TmDump;
+    /// #
+    /// let data = b"QQMU\x00\x00\x00\x00\x6D\x34\x61\x20";
+    /// let mut tm = TmDump::from_reader(Cursor::new(data)).unwrap();
+    /// let music = tm.get_data().unwrap();
+    /// assert_eq!(&music[4..], b"ftypm4a ");
+    /// ```
+    pub fn get_data(&mut self) -> std::io::Result<Vec<u8>> {
+        let mut output = Vec::new();
+        self.read_to_end(&mut output)?;
+        Ok(output)
+    }
+}
+
+impl<R> Read for TmDump<R>
+where
+    R: Read,
+{
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let size = if self.cursor < TM_HEADER_SIZE as u64 {
+            let header = &self.header[self.cursor as usize..];
+            let size = buf.len().min(header.len());
+            buf[..size].copy_from_slice(&header[..size]);
+            size
+        } else {
+            self.reader.read(buf)?
+        };
+        self.cursor += size as u64;
+        Ok(size)
+    }
+}
+
+impl<R> Seek for TmDump<R>
+where
+    R: Read + Seek,
+{
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        let position = match pos {
+            SeekFrom::Start(p) => p,
+            SeekFrom::Current(p) => self.cursor.checked_add_signed(p).ok_or_else(|| {
+                std::io::Error::new(std::io::ErrorKind::InvalidInput, "invalid seek")
+            })?,
+            SeekFrom::End(_) => self.reader.seek(pos)?,
+        };
+
+        // The reader is kept after the header, which is read from memory
+        self.reader
+            .seek(SeekFrom::Start(position.max(TM_HEADER_SIZE as u64)))?;
+        self.cursor = position;
+        Ok(self.cursor)
+    }
+}
+
+impl<R> Dump for TmDump<R>
+where
+    R: Read + Seek,
+{
+    fn get_audio_format(&mut self) -> Result<Option<AudioFormat>> {
+        Ok(AudioFormat::from_extension(self.format))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::io::Cursor;
+
+    use anyhow::Result;
+
+    use super::*;
+
     #[test]
     fn test_qmcdump_ok() -> Result<()> {
         let input = File::open("res/test.qmcflac")?;
@@ -209,4 +456,142 @@ mod tests {
         assert_eq!(buf, [0xD4, 0xC9]);
         Ok(())
     }
+
+    #[test]
+    fn test_qmcdump_from_seekable_reader_ok() -> Result<()> {
+        let mut data = std::fs::read("res/test.qmcflac")?;
+        data.extend_from_slice(b"12345,2,004ABCDE");
+        data.extend_from_slice(&16u32.to_be_bytes());
+        data.extend_from_slice(b"STag");
+
+        let mut qmc = QmcDump::from_seekable_reader(Cursor::new(data))?;
+        let data = qmc.get_data()?;
+        assert_eq!(data.len(), 4096);
+        assert_eq!(data[..4], [0x66, 0x4C, 0x61, 0x43]);
+
+        let mut buf = [0; 4];
+        assert_eq!(qmc.seek(SeekFrom::End(-2))?, 4094);
+        let size = qmc.read(&mut buf)?;
+        assert_eq!(size, 2);
+        assert_eq!(buf[..2], data[4094..]);
+        assert!(qmc.seek(SeekFrom::End(-4097)).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_qmcv2dump_key_not_found_err() {
+        let mut data = vec![0x00, 0x01, 0x02, 0x03];
+        data.extend_from_slice(b"12345,2,004ABCDE");
+        data.extend_from_slice(&16u32.to_be_bytes());
+        data.extend_from_slice(b"STag");
+        let result = QmcV2Dump::from_reader(Cursor::new(data));
+        assert!(matches!(result, Err(Errors::KeyNotFound)));
+
+        let result = QmcV2Dump::from_reader(Cursor::new([0x00, 0x01, 0x02, 0x03]));
+        assert!(matches!(result, Err(Errors::KeyNotFound)));
+    }
+
+    #[test]
+    fn test_qmcv2dump_with_key_ok() -> Result<()> {
+        let mut data = std::fs::read("res/test.mflac")?;
+        data.truncate(4096);
+        data.extend_from_slice(b"12345,2,004ABCDE");
+        data.extend_from_slice(&16u32.to_be_bytes());
+        data.extend_from_slice(b"STag");
+
+        let key = b"Ik2zwEQHfwcepYyNGfB51YbmwxAscRuzOl8G5UBBBpiA84YrNbuBhOwc8fjOWOrO\
+wd8S7Ba16j7pGLou3SHvV5utg79bg16qMTSl4f28gZl2CePvzZaqLXj4sxrvXFcq";
+        let mut qmc = QmcV2Dump::from_reader_with_key(Cursor::new(data), key)?;
+        let data = qmc.get_data()?;
+        assert_eq!(data.len(), 4096);
+        assert_eq!(data[..4], [0x66, 0x4C, 0x61, 0x43]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_qmcv2dump_get_data_ok() -> Result<()> {
+        let input = File::open("res/test.mflac")?;
+        let mut qmc = QmcV2Dump::from_reader(input)?;
+        let data = qmc.get_data()?;
+        assert_eq!(data.len(), 4096);
+        assert_eq!(data[..4], [0x66, 0x4C, 0x61, 0x43]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_qmcv2dump_seek_ok() -> Result<()> {
+        let input = File::open("res/test.mflac")?;
+        let mut qmc = QmcV2Dump::from_reader(input)?;
+        let mut buf = [0; 4];
+
+        qmc.seek(SeekFrom::Start(1))?;
+        let size = qmc.read(&mut buf)?;
+        assert_eq!(size, 4);
+        assert_eq!(buf, [0x4C, 0x61, 0x43, 0x00]);
+
+        qmc.seek(SeekFrom::End(-2))?;
+        let size = qmc.read(&mut buf)?;
+        assert_eq!(size, 2);
+        assert!(qmc.seek(SeekFrom::End(-4097)).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_qmcv2dump_rc4_get_data_ok() -> Result<()> {
+        let mut qmc = QmcDump::from_reader(File::open("res/test.qmcflac")?)?;
+        let expect = qmc.get_data()?;
+
+        let input = File::open("res/test_rc4.mflac")?;
+        let mut qmc = QmcV2Dump::from_reader(input)?;
+        let data = qmc.get_data()?;
+        assert_eq!(data.len(), 12288);
+        assert_eq!(data[..4096], expect);
+        assert_eq!(data[4096..8192], expect);
+        assert_eq!(data[8192..], expect);
+        Ok(())
+    }
+
+    #[test]
+    fn test_qmcv2dump_rc4_seek_ok() -> Result<()> {
+        let input = File::open("res/test_rc4.mflac")?;
+        let mut qmc = QmcV2Dump::from_reader(input)?;
+        let mut buf = [0; 4];
+
+        qmc.seek(SeekFrom::Start(4096))?;
+        let size = qmc.read(&mut buf)?;
+        assert_eq!(size, 4);
+        assert_eq!(buf, [0x66, 0x4C, 0x61, 0x43]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_tmdump_m4a_ok() -> Result<()> {
+        let mut data = b"QQMU\x01\x02\x03\x04".to_vec();
+        data.extend_from_slice(b"M4A \x00\x00\x00\x00");
+        let mut tm = TmDump::from_reader(Cursor::new(data))?;
+        assert_eq!(tm.get_format(), "m4a");
+        let data = tm.get_data()?;
+        assert_eq!(data, b"\x00\x00\x00\x20ftypM4A \x00\x00\x00\x00");
+
+        let mut buf = [0; 8];
+        tm.seek(SeekFrom::Start(4))?;
+        tm.read_exact(&mut buf)?;
+        assert_eq!(&buf, b"ftypM4A ");
+        Ok(())
+    }
+
+    #[test]
+    fn test_tmdump_mp3_ok() -> Result<()> {
+        let input = b"ID3\x04\x00\x00\x00\x00\x00\x00";
+        let mut tm = TmDump::from_reader(Cursor::new(input))?;
+        assert_eq!(tm.get_format(), "mp3");
+        assert_eq!(tm.get_data()?, input);
+        Ok(())
+    }
+
+    #[test]
+    fn test_tmdump_err() {
+        let result = TmDump::from_reader(Cursor::new(b"QQMU"));
+        assert!(matches!(result, Err(Errors::InvalidFileType)));
+    }
 }
@@ -0,0 +1,221 @@
+use std::io::{Read, Seek, SeekFrom};
+
+use crate::error::{Errors, Result};
+
+const MAX_TRAILER_LENGTH: u64 = 0x1000;
+
+/// The kind of trailer at the end of qmc file.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum QmcTrailerKind {
+    /// There is no trailer, the whole file is audio data.
+    None,
+    /// The raw base64 ekey, followed by its little-endian length.
+    Raw,
+    /// The `QTag` trailer, which contains the ekey, song id and version.
+    QTag,
+    /// The `STag` trailer, which contains the song id but no key.
+    STag,
+}
+
+/// The trailer of qmc file.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct QmcTrailer {
+    /// The kind of trailer
+    pub kind: QmcTrailerKind,
+    /// The length of audio data, without the trailer
+    pub length: u64,
+    /// The id of song, if the trailer contains it
+    pub song_id: Option<u64>,
+    /// The base64 encoded ekey, if the trailer contains it
+    pub ekey: Option<Vec<u8>>,
+}
+
+impl QmcTrailer {
+    fn is_base64(data: &[u8]) -> bool {
+        let data = match data.iter().rposition(|&byte| byte != 0) {
+            Some(index) => &data[..=index],
+            None => return false,
+        };
+        data.iter()
+            .all(|&byte| byte.is_ascii_alphanumeric() || matches!(byte, b'+' | b'/' | b'='))
+    }
+
+    /// Read the tag content before the tag name and its big-endian length.
+    fn read_tag<R>(reader: &mut R, end: u64) -> Result<(u64, Vec<u8>)>
+    where
+        R: Read + Seek,
+    {
+        let mut tag_length = [0; 4];
+        reader.seek(SeekFrom::Start(end - 8))?;
+        reader.read_exact(&mut tag_length)?;
+        let tag_length = u32::from_be_bytes(tag_length) as u64;
+        if tag_length > MAX_TRAILER_LENGTH || tag_length + 8 > end {
+            return Err(Errors::InvalidTrailer);
+        }
+
+        let length = end - 8 - tag_length;
+        let mut tag = vec![0; tag_length as usize];
+        reader.seek(SeekFrom::Start(length))?;
+        reader.read_exact(&mut tag)?;
+        Ok((length, tag))
+    }
+
+    /// Parse the trailer at the end of reader.
+    /// The position of reader is undefined after parsing.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use std::fs::File;
+    /// #
+    /// # use ncmdump::QmcTrailer;
+    /// #
+    /// let mut file = File::open("res/test.mflac").expect("Can't open file");
+    /// let trailer = QmcTrailer::parse(&mut file).unwrap();
+    /// println!("{:?}", trailer.kind);
+    /// ```
+    pub fn parse<R>(reader: &mut R) -> Result<Self>
+    where
+        R: Read + Seek,
+    {
+        let end = reader.seek(SeekFrom::End(0))?;
+        let mut tail = [0; 4];
+        if end < 8 {
+            return Ok(Self {
+                kind: QmcTrailerKind::None,
+                length: end,
+                song_id: None,
+                ekey: None,
+            });
+        }
+        reader.seek(SeekFrom::Start(end - 4))?;
+        reader.read_exact(&mut tail)?;
+
+        match &tail {
+            b"QTag" => {
+                let (length, tag) = Self::read_tag(reader, end)?;
+                let mut fields = tag.split(|&byte| byte == b',');
+                let ekey = fields.next().filter(|ekey| !ekey.is_empty());
+                let song_id = fields
+                    .next()
+                    .and_then(|id| std::str::from_utf8(id).ok())
+                    .and_then(|id| id.parse().ok());
+                Ok(Self {
+                    kind: QmcTrailerKind::QTag,
+                    length,
+                    song_id,
+                    ekey: Some(ekey.ok_or(Errors::InvalidTrailer)?.to_vec()),
+                })
+            }
+            b"STag" => {
+                let (length, tag) = Self::read_tag(reader, end)?;
+                let song_id = tag
+                    .split(|&byte| byte == b',')
+                    .next()
+                    .and_then(|id| std::str::from_utf8(id).ok())
+                    .and_then(|id| id.parse().ok());
+                Ok(Self {
+                    kind: QmcTrailerKind::STag,
+                    length,
+                    song_id,
+                    ekey: None,
+                })
+            }
+            _ => {
+                let key_length = u32::from_le_bytes(tail) as u64;
+                if key_length > 0 && key_length <= MAX_TRAILER_LENGTH && key_length + 4 <= end {
+                    let length = end - 4 - key_length;
+                    let mut ekey = vec![0; key_length as usize];
+                    reader.seek(SeekFrom::Start(length))?;
+                    reader.read_exact(&mut ekey)?;
+                    if Self::is_base64(&ekey) {
+                        return Ok(Self {
+                            kind: QmcTrailerKind::Raw,
+                            length,
+                            song_id: None,
+                            ekey: Some(ekey),
+                        });
+                    }
+                }
+                Ok(Self {
+                    kind: QmcTrailerKind::None,
+                    length: end,
+                    song_id: None,
+                    ekey: None,
+                })
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::io::Cursor;
+
+    use anyhow::Result;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_raw_ok() -> Result<()> {
+        let mut input = File::open("res/test.mflac")?;
+        let trailer = QmcTrailer::parse(&mut input)?;
+        assert_eq!(trailer.kind, QmcTrailerKind::Raw);
+        assert_eq!(trailer.length, 4096);
+        assert_eq!(trailer.song_id, None);
+        assert_eq!(trailer.ekey.map(|ekey| ekey.len()), Some(192));
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_none_ok() -> Result<()> {
+        let mut input = File::open("res/test.qmcflac")?;
+        let trailer = QmcTrailer::parse(&mut input)?;
+        assert_eq!(trailer.kind, QmcTrailerKind::None);
+        assert_eq!(trailer.length, 4096);
+
+        let mut input = Cursor::new([0x00, 0x01, 0x02]);
+        let trailer = QmcTrailer::parse(&mut input)?;
+        assert_eq!(trailer.kind, QmcTrailerKind::None);
+        assert_eq!(trailer.length, 3);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_qtag_ok() -> Result<()> {
+        let mut data = vec![0x00, 0x01, 0x02, 0x03];
+        data.extend_from_slice(b"ZWtleQ==,12345,2");
+        data.extend_from_slice(&16u32.to_be_bytes());
+        data.extend_from_slice(b"QTag");
+        let trailer = QmcTrailer::parse(&mut Cursor::new(data))?;
+        assert_eq!(trailer.kind, QmcTrailerKind::QTag);
+        assert_eq!(trailer.length, 4);
+        assert_eq!(trailer.song_id, Some(12345));
+        assert_eq!(trailer.ekey, Some(b"ZWtleQ==".to_vec()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_stag_ok() -> Result<()> {
+        let mut data = vec![0x00, 0x01, 0x02, 0x03];
+        data.extend_from_slice(b"12345,2,004ABCDE");
+        data.extend_from_slice(&16u32.to_be_bytes());
+        data.extend_from_slice(b"STag");
+        let trailer = QmcTrailer::parse(&mut Cursor::new(data))?;
+        assert_eq!(trailer.kind, QmcTrailerKind::STag);
+        assert_eq!(trailer.length, 4);
+        assert_eq!(trailer.song_id, Some(12345));
+        assert_eq!(trailer.ekey, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_qtag_err() {
+        let mut data = vec![0x00, 0x01, 0x02, 0x03];
+        data.extend_from_slice(&16u32.to_be_bytes());
+        data.extend_from_slice(b"QTag");
+        let result = QmcTrailer::parse(&mut Cursor::new(data));
+        assert!(matches!(result, Err(Errors::InvalidTrailer)));
+    }
+}
@@ -0,0 +1,39 @@
+//! The `no_std` core of the ncm and qmc dumpers.
+//!
+//! It only works on the byte slices and the offsets, and needs nothing but `alloc`.
+//! The dumpers with `std::io` are built on it behind the default `std` feature.
+//!
+//! # Example
+//!
+//! ```rust
+//! use ncmdump::raw::qmc;
+//!
+//! let mut data = [0xA5, 0x06, 0xB7, 0x89];
+//! qmc::encrypt(0, &mut data);
+//! assert_eq!(&data, b"fLaC");
+//! ```
+#[cfg(any(feature = "ncmdump", feature = "qmcdump"))]
+use alloc::vec::Vec;
+
+#[cfg(feature = "ncmdump")]
+pub mod ncm;
+#[cfg(feature = "qmcdump")]
+pub mod qmc;
+
+/// The event of the sans-IO parsers, which is returned in the order of file.
+#[cfg(any(feature = "ncmdump", feature = "qmcdump"))]
+#[derive(Debug, Eq, PartialEq)]
+pub enum Event {
+    /// The input is not enough, it's the least length of data to continue
+    NeedMoreData(usize),
+    /// The key is parsed, the key box is ready
+    Key,
+    /// The encrypted information, which is decoded by `ncm::decode_info`
+    Info(Vec<u8>),
+    /// The cover image
+    Cover(Vec<u8>),
+    /// The other image after the cover in the cover frame
+    Image(Vec<u8>),
+    /// The decrypted music data
+    Audio(Vec<u8>),
+}
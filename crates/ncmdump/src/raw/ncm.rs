@@ -0,0 +1,979 @@
+//! The ncm cipher and header layout.
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use aes::Aes128;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use cipher::block_padding::Pkcs7;
+use cipher::{BlockDecryptMut, KeyInit};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Errors, Result};
+use crate::raw::Event;
+
+const HEADER_KEY: [u8; 16] = [
+    0x68, 0x7A, 0x48, 0x52, 0x41, 0x6D, 0x73, 0x6F, 0x35, 0x6B, 0x49, 0x6E, 0x62, 0x61, 0x78, 0x57,
+];
+
+const INFO_KEY: [u8; 16] = [
+    0x23, 0x31, 0x34, 0x6C, 0x6A, 0x6B, 0x5F, 0x21, 0x5C, 0x5D, 0x26, 0x30, 0x55, 0x3C, 0x27, 0x28,
+];
+
+#[derive(Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(untagged)]
+pub(crate) enum NcmId {
+    String(String),
+    Integer(u64),
+}
+
+/// The ncm file information.
+#[derive(Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub(crate) struct RawNcmInfo {
+    /// The name of music
+    #[serde(rename = "musicName")]
+    pub name: String,
+    /// The id of music
+    #[serde(rename = "musicId")]
+    pub id: NcmId,
+    /// The album of music, it's an url
+    pub album: String,
+    /// The artist of music, first item is name, second item is id
+    pub artist: Vec<(String, NcmId)>,
+    // The bit rate of music
+    pub bitrate: NcmId,
+    /// The duration of music
+    pub duration: NcmId,
+    /// The format of music, is maybe 'mp3' or 'flac'
+    pub format: String,
+    /// The id of MV
+    #[serde(rename = "mvId")]
+    pub mv_id: Option<NcmId>,
+    /// The alias of music
+    pub alias: Option<Vec<String>>,
+}
+
+/// The index of uc cache file, which is saved beside it as a `.idx` file.
+#[derive(Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub(crate) struct RawUcIndex {
+    /// The id of music
+    #[serde(rename = "musicId")]
+    pub id: NcmId,
+    /// The name of music
+    #[serde(rename = "musicName")]
+    pub name: Option<String>,
+    /// The album of music
+    pub album: Option<String>,
+    /// The artist of music, first item is name, second item is id
+    pub artist: Option<Vec<(String, NcmId)>>,
+    // The bit rate of music
+    pub bitrate: Option<NcmId>,
+    /// The duration of music
+    pub duration: Option<NcmId>,
+    /// The format of music, is maybe 'mp3' or 'flac'
+    pub format: Option<String>,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub struct NcmInfo {
+    pub name: String,
+    /// The id of music
+    pub id: u64,
+    /// The album of music, it's an url
+    pub album: String,
+    /// The artist of music, first item is name, second item is id
+    pub artist: Vec<(String, u64)>,
+    // The bit rate of music
+    pub bitrate: u64,
+    /// The duration of music
+    pub duration: u64,
+    /// The format of music, is maybe 'mp3' or 'flac'
+    pub format: String,
+    /// The id of MV
+    pub mv_id: Option<u64>,
+    /// The alias of music
+    pub alias: Option<Vec<String>>,
+}
+
+/// The layout of ncm header, the ranges are the offsets from the start of file.
+///
+/// The key, the information and the cover frame are preceded by their lengths,
+/// and the cover frame is also preceded by the length of image, every length is
+/// a little-endian `u32`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct NcmHeader {
+    /// The magic header `CTENFDAM`
+    pub magic: Range<usize>,
+    /// The gap after the magic header
+    pub magic_gap: Range<usize>,
+    /// The encrypted key
+    pub key: Range<usize>,
+    /// The encrypted information, it's empty in the old version
+    pub info: Range<usize>,
+    /// The crc of information
+    pub crc: Range<usize>,
+    /// The gap after the crc
+    pub crc_gap: Range<usize>,
+    /// The cover frame, which starts with the cover image
+    pub frame: Range<usize>,
+    /// The cover image, it's empty if there is no cover
+    pub image: Range<usize>,
+    /// The offset of music data
+    pub audio_offset: usize,
+    /// The decrypted rc4 key, the prefix `neteasecloudmusic` is skipped
+    pub rc4_key: Vec<u8>,
+    /// The key box which is built from the rc4 key
+    pub key_box: [u8; 256],
+}
+
+impl From<RawNcmInfo> for NcmInfo {
+    fn from(raw_info: RawNcmInfo) -> Self {
+        Self {
+            name: raw_info.name,
+            id: raw_info.id.get_id().unwrap_or(0),
+            album: raw_info.album,
+            artist: raw_info
+                .artist
+                .into_iter()
+                .map(|(name, id)| (name, id.get_id().unwrap_or(0)))
+                .collect::<Vec<(String, u64)>>(),
+            bitrate: raw_info.bitrate.get_id().unwrap_or(0),
+            duration: raw_info.duration.get_id().unwrap_or(0),
+            format: raw_info.format,
+            mv_id: match raw_info.mv_id {
+                Some(id) => id.get_id().ok(),
+                None => None,
+            },
+            alias: raw_info.alias,
+        }
+    }
+}
+
+impl From<RawUcIndex> for NcmInfo {
+    fn from(raw_index: RawUcIndex) -> Self {
+        Self {
+            name: raw_index.name.unwrap_or_default(),
+            id: raw_index.id.get_id().unwrap_or(0),
+            album: raw_index.album.unwrap_or_default(),
+            artist: raw_index
+                .artist
+                .unwrap_or_default()
+                .into_iter()
+                .map(|(name, id)| (name, id.get_id().unwrap_or(0)))
+                .collect::<Vec<(String, u64)>>(),
+            bitrate: raw_index
+                .bitrate
+                .and_then(|id| id.get_id().ok())
+                .unwrap_or(0),
+            duration: raw_index
+                .duration
+                .and_then(|id| id.get_id().ok())
+                .unwrap_or(0),
+            format: raw_index.format.unwrap_or_default(),
+            mv_id: None,
+            alias: None,
+        }
+    }
+}
+
+impl NcmId {
+    pub(crate) fn get_id(self) -> Result<u64> {
+        match self {
+            NcmId::String(s) => {
+                if s.is_empty() {
+                    return Err(Errors::InfoDecodeError);
+                }
+                s.parse().map_err(|_| Errors::InfoDecodeError)
+            }
+            NcmId::Integer(num) => Ok(num),
+        }
+    }
+}
+
+impl NcmHeader {
+    /// Build the layout from the length of every section.
+    /// The lengths are read from the file, so the overflow of offsets is an error.
+    fn new(
+        key_length: usize,
+        info_length: usize,
+        cover_frame_length: usize,
+        image_length: usize,
+        rc4_key: Vec<u8>,
+    ) -> Result<Self> {
+        let key_end = checked_add(14, key_length, Errors::InvalidKeyLength)?;
+        let key = 14..key_end;
+        let info_start = checked_add(key.end, 4, Errors::InvalidInfoLength)?;
+        let info_end = checked_add(info_start, info_length, Errors::InvalidInfoLength)?;
+        let info = info_start..info_end;
+        let crc = info.end..info.end + 4;
+        let crc_gap = crc.end..crc.end + 1;
+
+        // The cover frame starts with the cover, and the other image follows it
+        let frame_start = checked_add(info.end, 13, Errors::InvalidImageLength)?;
+        let frame_end = checked_add(
+            frame_start,
+            cover_frame_length.max(image_length),
+            Errors::InvalidImageLength,
+        )?;
+        let frame = frame_start..frame_end;
+        Ok(Self {
+            magic: 0..8,
+            magic_gap: 8..10,
+            key,
+            info,
+            crc,
+            crc_gap,
+            image: frame_start..frame_start + image_length,
+            audio_offset: frame.end,
+            frame,
+            key_box: build_key_box(&rc4_key),
+            rc4_key,
+        })
+    }
+
+    /// Parse the header from the start of file.
+    ///
+    /// The buffer must contain the header until the length of image,
+    /// the cover frame itself is not needed.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use std::fs;
+    /// #
+    /// use ncmdump::raw::ncm::{self, NcmHeader};
+    ///
+    /// let data = fs::read("res/test.ncm").expect("Can't read file");
+    /// let header = NcmHeader::parse(&data).unwrap();
+    /// let mut music = data[header.audio_offset..].to_vec();
+    /// ncm::encrypt(&header.key_box, 0, &mut music);
+    /// assert!(music.starts_with(b"fLaC"));
+    /// ```
+    pub fn parse(buffer: &[u8]) -> Result<Self> {
+        if !check_format(buffer) || buffer.len() < 10 {
+            return Err(Errors::InvalidFileType);
+        }
+
+        let key_length = read_u32(buffer, 10, Errors::InvalidKeyLength)?;
+        let key_end = checked_add(14, key_length, Errors::InvalidKeyLength)?;
+        let key = buffer.get(14..key_end).ok_or(Errors::InvalidKeyLength)?;
+        let rc4_key = decrypt_key(key)?;
+
+        let info_length = read_u32(buffer, key_end, Errors::InvalidInfoLength)?;
+        let info_end = checked_add(key_end + 4, info_length, Errors::InvalidInfoLength)?;
+        if buffer.len() < info_end {
+            return Err(Errors::InvalidInfoLength);
+        }
+
+        // Skip the crc and the gap
+        let cover_frame_length = read_u32(buffer, info_end + 5, Errors::InvalidImageLength)?;
+        let image_length = read_u32(buffer, info_end + 9, Errors::InvalidImageLength)?;
+        Self::new(
+            key_length,
+            info_length,
+            cover_frame_length,
+            image_length,
+            rc4_key,
+        )
+    }
+}
+
+/// Get the length of header, which is also the offset of music data.
+///
+/// It returns `None` if the buffer is too short to know every length of the header,
+/// more data should be read before parsing the header.
+pub fn header_length(buffer: &[u8]) -> Option<usize> {
+    let key_length = read_u32(buffer, 10, Errors::InvalidKeyLength).ok()?;
+    let key_end = key_length.checked_add(14)?;
+    let info_length = read_u32(buffer, key_end, Errors::InvalidInfoLength).ok()?;
+    let info_end = (key_end + 4).checked_add(info_length)?;
+    let frame_start = info_end.checked_add(13)?;
+    let cover_frame_length = read_u32(buffer, frame_start - 8, Errors::InvalidImageLength).ok()?;
+    let image_length = read_u32(buffer, frame_start - 4, Errors::InvalidImageLength).ok()?;
+    frame_start.checked_add(cover_frame_length.max(image_length))
+}
+
+/// Read the little-endian length at the offset.
+fn read_u32(buffer: &[u8], offset: usize, error: Errors) -> Result<usize> {
+    let bytes = offset
+        .checked_add(4)
+        .and_then(|end| buffer.get(offset..end))
+        .ok_or(error)?;
+    let mut length = [0; 4];
+    length.copy_from_slice(bytes);
+    Ok(u32::from_le_bytes(length) as usize)
+}
+
+/// Add the offset and the length read from file, the overflow is returned as the error.
+fn checked_add(offset: usize, length: usize, error: Errors) -> Result<usize> {
+    offset.checked_add(length).ok_or(error)
+}
+
+/// The state of parser, which is the section to be parsed.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum State {
+    Magic,
+    KeyLength,
+    Key(usize),
+    InfoLength,
+    Info(usize),
+    FrameLength,
+    Cover { length: usize, other: usize },
+    Image(usize),
+    Audio,
+}
+
+impl State {
+    /// Get the length of section.
+    fn length(self) -> usize {
+        match self {
+            State::Magic => 10,
+            State::KeyLength | State::InfoLength => 4,
+            // The crc, the gap, the length of cover frame and the length of image
+            State::FrameLength => 13,
+            State::Key(length) | State::Info(length) | State::Image(length) => length,
+            State::Cover { length, .. } => length,
+            State::Audio => 0,
+        }
+    }
+
+    /// Get the error if the data ends in this section.
+    fn error(self) -> Errors {
+        match self {
+            State::Magic => Errors::InvalidFileType,
+            State::KeyLength | State::Key(_) => Errors::InvalidKeyLength,
+            State::InfoLength | State::Info(_) => Errors::InvalidInfoLength,
+            _ => Errors::InvalidImageLength,
+        }
+    }
+}
+
+/// The sans-IO parser of ncm file.
+///
+/// The data is pushed by [`Parser::parse`], and the sections of file are returned
+/// as [`Event`]s in order, so it can be used with any kind of IO.
+///
+/// # Example
+///
+/// ```rust
+/// # use std::fs;
+/// #
+/// use ncmdump::raw::ncm::Parser;
+/// use ncmdump::raw::Event;
+///
+/// let data = fs::read("res/test.ncm").expect("Can't read file");
+/// let mut parser = Parser::new();
+/// let mut music = Vec::new();
+/// for chunk in data.chunks(4096) {
+///     let mut chunk = chunk;
+///     loop {
+///         let (size, event) = parser.parse(chunk).unwrap();
+///         chunk = &chunk[size..];
+///         match event {
+///             Event::NeedMoreData(_) => break,
+///             Event::Audio(data) => music.extend(data),
+///             _ => {}
+///         }
+///     }
+/// }
+/// parser.finish().unwrap();
+/// assert!(music.starts_with(b"fLaC"));
+/// ```
+#[derive(Debug)]
+pub struct Parser {
+    state: State,
+    buffer: Vec<u8>,
+    key_box: [u8; 256],
+    rc4_key: Vec<u8>,
+    key_length: usize,
+    info_length: usize,
+    header: Option<NcmHeader>,
+    position: u64,
+    cursor: u64,
+}
+
+impl Default for Parser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Parser {
+    /// Create the parser at the start of file.
+    pub fn new() -> Self {
+        Self {
+            state: State::Magic,
+            buffer: Vec::new(),
+            key_box: [0; 256],
+            rc4_key: Vec::new(),
+            key_length: 0,
+            info_length: 0,
+            header: None,
+            position: 0,
+            cursor: 0,
+        }
+    }
+
+    /// Get the key box, it's `None` before the key is parsed.
+    pub fn key_box(&self) -> Option<&[u8; 256]> {
+        match self.state {
+            State::Magic | State::KeyLength | State::Key(_) => None,
+            _ => Some(&self.key_box),
+        }
+    }
+
+    /// Get the layout of header, it's `None` before the length of image is parsed.
+    pub fn header(&self) -> Option<&NcmHeader> {
+        self.header.as_ref()
+    }
+
+    /// Take the layout of header, the error is returned if the data ends in the header.
+    pub fn into_header(self) -> Result<NcmHeader> {
+        match self.header {
+            Some(header) if self.is_audio() => Ok(header),
+            _ => Err(self.state.error()),
+        }
+    }
+
+    /// Get the length of parsed data, it's the offset of music data after the header is parsed.
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    /// Check if the header is parsed, every data after it is music data.
+    pub fn is_audio(&self) -> bool {
+        self.state == State::Audio
+    }
+
+    /// Check the end of data, the error is returned if the data ends in the header.
+    pub fn finish(&self) -> Result<()> {
+        match self.state {
+            State::Audio => Ok(()),
+            state => Err(state.error()),
+        }
+    }
+
+    /// Parse the input, and return the length of consumed input with the next event.
+    ///
+    /// The input is consumed until an event is found, the rest of input should be passed
+    /// again. If the input is not enough, it's kept in the parser and
+    /// [`Event::NeedMoreData`] is returned with the least length to continue.
+    pub fn parse(&mut self, input: &[u8]) -> Result<(usize, Event)> {
+        let mut consumed = 0;
+        loop {
+            let rest = &input[consumed..];
+            if self.state == State::Audio {
+                if rest.is_empty() {
+                    return Ok((consumed, Event::NeedMoreData(1)));
+                }
+                let mut data = rest.to_vec();
+                encrypt(&self.key_box, self.cursor, &mut data);
+                self.cursor += data.len() as u64;
+                self.position += data.len() as u64;
+                return Ok((input.len(), Event::Audio(data)));
+            }
+
+            let length = self.state.length();
+            let size = (length - self.buffer.len()).min(rest.len());
+            self.buffer.extend_from_slice(&rest[..size]);
+            self.position += size as u64;
+            consumed += size;
+            if self.buffer.len() < length {
+                return Ok((consumed, Event::NeedMoreData(length - self.buffer.len())));
+            }
+
+            let section = core::mem::take(&mut self.buffer);
+            if let Some(event) = self.next_state(section)? {
+                return Ok((consumed, event));
+            }
+        }
+    }
+
+    /// Move to the next state with the section, the event of section is returned.
+    fn next_state(&mut self, section: Vec<u8>) -> Result<Option<Event>> {
+        let (state, event) = match self.state {
+            State::Magic => {
+                if !check_format(&section) {
+                    return Err(Errors::InvalidFileType);
+                }
+                (State::KeyLength, None)
+            }
+            State::KeyLength => {
+                let length = read_u32(&section, 0, Errors::InvalidKeyLength)?;
+                (State::Key(length), None)
+            }
+            State::Key(length) => {
+                self.key_length = length;
+                self.rc4_key = decrypt_key(&section)?;
+                self.key_box = build_key_box(&self.rc4_key);
+                (State::InfoLength, Some(Event::Key))
+            }
+            State::InfoLength => {
+                let length = read_u32(&section, 0, Errors::InvalidInfoLength)?;
+                (State::Info(length), None)
+            }
+            State::Info(length) => {
+                self.info_length = length;
+                (State::FrameLength, Some(Event::Info(section)))
+            }
+            State::FrameLength => {
+                let cover_frame_length = read_u32(&section, 5, Errors::InvalidImageLength)?;
+                let length = read_u32(&section, 9, Errors::InvalidImageLength)?;
+                self.header = Some(NcmHeader::new(
+                    self.key_length,
+                    self.info_length,
+                    cover_frame_length,
+                    length,
+                    core::mem::take(&mut self.rc4_key),
+                )?);
+
+                // The cover frame starts with the cover, and the other image follows it
+                let other = cover_frame_length.saturating_sub(length);
+                (State::Cover { length, other }, None)
+            }
+            State::Cover { other, .. } => {
+                let event = (!section.is_empty()).then_some(Event::Cover(section));
+                (State::Image(other), event)
+            }
+            State::Image(_) => {
+                let event = (!section.is_empty()).then_some(Event::Image(section));
+                (State::Audio, event)
+            }
+            State::Audio => (State::Audio, None),
+        };
+        self.state = state;
+        Ok(event)
+    }
+}
+
+/// Decrypt the key area of file, the prefix `neteasecloudmusic` is skipped.
+pub fn decrypt_key(key: &[u8]) -> Result<Vec<u8>> {
+    let key_buffer = key.iter().map(|byte| byte ^ 0x64).collect::<Vec<u8>>();
+    let decrypt_buffer = decrypt(&key_buffer, &HEADER_KEY)?;
+    let key = decrypt_buffer.get(17..).ok_or(Errors::InvalidKeyLength)?;
+    Ok(key.to_vec())
+}
+
+/// Encrypt or decrypt the music data in place, the offset is from the start of music data.
+pub fn encrypt(key_box: &[u8; 256], offset: u64, buffer: &mut [u8]) {
+    for (i, byte) in buffer.iter_mut().enumerate() {
+        let j = ((offset + i as u64 + 1) & 0xff) as usize;
+        let k = (key_box[j].wrapping_add(j as u8)) as usize;
+        let key_index = key_box[k].wrapping_add(key_box[j]) as usize;
+        *byte ^= key_box[key_index]
+    }
+}
+
+fn decrypt(data: &[u8], key: &[u8; 16]) -> Result<Vec<u8>> {
+    let result = Aes128::new(key.into())
+        .decrypt_padded_vec_mut::<Pkcs7>(data)
+        .map_err(|_| Errors::DecryptError)?;
+    Ok(result)
+}
+
+/// Build the rc4 key box from the decrypted key.
+pub fn build_key_box(key: &[u8]) -> [u8; 256] {
+    let mut j = 0;
+    let mut key_box = [0u8; 256];
+    key_box
+        .iter_mut()
+        .enumerate()
+        .for_each(|(i, k)| *k = i as u8);
+
+    let key_stream = key.iter().cycle();
+    for (i, &k) in (0..256).zip(key_stream) {
+        j = key_box[i].wrapping_add(j).wrapping_add(k);
+        key_box.swap(i, j as usize);
+    }
+    key_box
+}
+
+/// Check the file format by header.
+pub fn check_format(buffer: &[u8]) -> bool {
+    buffer.starts_with(b"CTENFDAM")
+}
+
+/// Decode the information buffer, which is empty in the old version.
+pub fn decode_info(info_bytes: &[u8]) -> Result<Option<NcmInfo>> {
+    if info_bytes.is_empty() {
+        return Ok(None);
+    }
+    let info_tmp = info_bytes
+        .iter()
+        .map(|item| item ^ 0x63)
+        .collect::<Vec<u8>>();
+
+    // Skip the prefix `163 key(Don't modify):`
+    let info_key = STANDARD
+        .decode(info_tmp.get(22..).ok_or(Errors::InfoDecodeError)?)
+        .map_err(|_| Errors::InfoDecodeError)?;
+    let info_data = decrypt(&info_key, &INFO_KEY)?;
+
+    // Skip the prefix `music:`
+    let info_str = String::from_utf8(info_data.get(6..).ok_or(Errors::InfoDecodeError)?.to_vec())
+        .map_err(|_| Errors::InfoDecodeError)?;
+    let info =
+        serde_json::from_str::<RawNcmInfo>(&info_str).map_err(|_| Errors::InfoDecodeError)?;
+    Ok(Some(NcmInfo::from(info)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use anyhow::Result;
+
+    use super::*;
+
+    #[test]
+    fn test_header_parse_ok() -> Result<()> {
+        let data = fs::read("res/test.ncm")?;
+        let header = NcmHeader::parse(&data)?;
+        assert_eq!(&data[header.magic.clone()], b"CTENFDAM");
+        assert_eq!(header.magic_gap, 8..10);
+        assert_eq!(header.key, 14..142);
+        assert_eq!(header.info.start, header.key.end + 4);
+        assert!(decode_info(&data[header.info.clone()])?.is_some());
+        assert_eq!(header.crc, header.info.end..header.info.end + 4);
+        assert_eq!(header.crc_gap, header.crc.end..header.crc.end + 1);
+        assert_eq!(header.frame.start, header.crc_gap.end + 8);
+        assert_eq!(header.image.start, header.frame.start);
+        assert_eq!(header.key_box, build_key_box(&header.rc4_key));
+        assert_eq!(header_length(&data), Some(header.audio_offset));
+        assert_eq!(header_length(&data[..header.image.start - 1]), None);
+
+        let mut music = data[header.audio_offset..][..4].to_vec();
+        encrypt(&header.key_box, 0, &mut music);
+        assert_eq!(music, b"fLaC");
+        Ok(())
+    }
+
+    #[test]
+    fn test_header_parse_err() -> Result<()> {
+        let data = fs::read("res/test.ncm")?;
+        let result = NcmHeader::parse(&data[..8]);
+        assert!(matches!(result, Err(Errors::InvalidFileType)));
+        let result = NcmHeader::parse(&data[..100]);
+        assert!(matches!(result, Err(Errors::InvalidKeyLength)));
+        let result = NcmHeader::parse(b"CTENFDAM\0\0\x04\0\0\0\0\0");
+        assert!(matches!(result, Err(Errors::InvalidKeyLength)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_header_new_overflow_err() {
+        let result = NcmHeader::new(usize::MAX - 8, 0, 0, 0, Vec::new());
+        assert!(matches!(result, Err(Errors::InvalidKeyLength)));
+        let result = NcmHeader::new(128, usize::MAX - 140, 0, 0, Vec::new());
+        assert!(matches!(result, Err(Errors::InvalidInfoLength)));
+        let result = NcmHeader::new(128, 0, 0, usize::MAX - 150, Vec::new());
+        assert!(matches!(result, Err(Errors::InvalidImageLength)));
+    }
+
+    #[test]
+    fn test_parser_ok() -> Result<()> {
+        let data = fs::read("res/test.ncm")?;
+        let header = NcmHeader::parse(&data)?;
+
+        let mut parser = Parser::new();
+        let mut events = Vec::new();
+        let mut music = Vec::new();
+        for chunk in data.chunks(1000) {
+            let mut chunk = chunk;
+            loop {
+                let (size, event) = parser.parse(chunk)?;
+                chunk = &chunk[size..];
+                match event {
+                    Event::NeedMoreData(_) => break,
+                    Event::Audio(data) => music.extend(data),
+                    event => events.push(event),
+                }
+            }
+        }
+        parser.finish()?;
+        assert_eq!(parser.key_box(), Some(&header.key_box));
+        assert_eq!(
+            events,
+            vec![
+                Event::Key,
+                Event::Info(data[header.info.clone()].to_vec()),
+                Event::Cover(data[header.image.clone()].to_vec()),
+            ]
+        );
+        assert_eq!(music.len(), data.len() - header.frame.end);
+        assert!(music.starts_with(b"fLaC"));
+        assert_eq!(parser.into_header()?, header);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parser_need_more_data_ok() -> Result<()> {
+        let data = fs::read("res/test.ncm")?;
+        let mut parser = Parser::new();
+        assert_eq!(parser.parse(&data[..4])?, (4, Event::NeedMoreData(6)));
+        assert_eq!(parser.parse(&data[4..14])?, (10, Event::NeedMoreData(128)));
+        assert_eq!(parser.key_box(), None);
+        assert_eq!(parser.parse(&data[14..142])?, (128, Event::Key));
+        assert_eq!(parser.header(), None);
+        assert!(parser.key_box().is_some());
+        assert_eq!(parser.position(), 142);
+        assert!(matches!(parser.finish(), Err(Errors::InvalidInfoLength)));
+        assert!(matches!(
+            parser.into_header(),
+            Err(Errors::InvalidInfoLength)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_parser_err() {
+        let mut parser = Parser::new();
+        let result = parser.parse(b"NOTANCMFILE");
+        assert!(matches!(result, Err(Errors::InvalidFileType)));
+        assert!(matches!(parser.finish(), Err(Errors::InvalidFileType)));
+    }
+
+    #[test]
+    fn test_ncm_info_convert_ok() {
+        let info = NcmInfo::from(RawNcmInfo {
+            name: "".to_string(),
+            id: NcmId::String(String::from("")),
+            album: "".to_string(),
+            artist: vec![],
+            bitrate: NcmId::String(String::from("")),
+            duration: NcmId::String(String::from("")),
+            format: "".to_string(),
+            mv_id: None,
+            alias: None,
+        });
+        assert_eq!(info.id, 0);
+        assert_eq!(info.artist, Vec::new());
+        assert_eq!(info.bitrate, 0);
+        assert_eq!(info.duration, 0);
+    }
+
+    #[test]
+    fn test_ncm_info_deserialize_golden_hour_ok() -> Result<()> {
+        let raw = serde_json::from_slice::<RawNcmInfo>(
+            br#"{
+    "musicId": 1958557540,
+    "musicName": "golden hour",
+    "artist": [
+        [
+            "JVKE",
+            32988392
+        ]
+    ],
+    "albumId": 152231212,
+    "album": "this is what ____ feels like (Vol. 1-4)",
+    "albumPicDocId": "109951167909857256",
+    "albumPic": "https://p3.music.126.net/xKRQRZxEClE6653o7NbHiw==/109951167909857256.jpg",
+    "bitrate": 320000,
+    "mp3DocId": "d919d45cba30d6d5e5daff43b71cca8a",
+    "duration": 209259,
+    "mvId": 14570752,
+    "alias": [],
+    "transNames": [],
+    "format": "mp3",
+    "flag": 260
+}"#,
+        )?;
+        let info = NcmInfo::from(raw);
+        assert_eq!(info.id, 1958557540);
+        assert_eq!(info.artist, vec!((String::from("JVKE"), 32988392),));
+        assert_eq!(info.bitrate, 320000);
+        assert_eq!(info.duration, 209259);
+        assert_eq!(info.mv_id, Some(14570752));
+        Ok(())
+    }
+
+    #[test]
+    fn test_ncm_info_deserialize_endless_summer_ok() -> Result<()> {
+        let raw = serde_json::from_slice::<RawNcmInfo>(
+            br#"{
+    "musicId": "2062898402",
+    "musicName": "Endless Summer",
+    "artist": [
+        [
+            "Alan Walker",
+            "1045123"
+        ],
+        [
+            "Zak Abel",
+            "1053190"
+        ]
+    ],
+    "albumId": "178429151",
+    "album": "Walkerworld",
+    "albumPicDocId": "109951169035717968",
+    "albumPic": "http://p3.music.126.net/V4y-2EL08KAaHWLa5wYJFw==/109951169035717968.jpg",
+    "bitrate": 320000,
+    "mp3DocId": "d0e253a78e474075cced9a9f7a35498c",
+    "duration": 187040,
+    "mvId": "14648342",
+    "transNames": [],
+    "format": "mp3",
+    "fee": 8,
+    "volumeDelta": -10.2386,
+    "privilege": {
+        "flag": 1277956
+    }
+}"#,
+        )?;
+        let info = NcmInfo::from(raw);
+        assert_eq!(info.id, 2062898402);
+        assert_eq!(
+            info.artist,
+            vec!(
+                (String::from("Alan Walker"), 1045123),
+                (String::from("Zak Abel"), 1053190)
+            )
+        );
+        assert_eq!(info.bitrate, 320000);
+        assert_eq!(info.duration, 187040);
+        assert_eq!(info.mv_id, Some(14648342));
+        Ok(())
+    }
+
+    #[test]
+    fn test_ncm_info_deserialize_eternal_regret_ok() -> Result<()> {
+        let raw = serde_json::from_slice::<RawNcmInfo>(
+            br#"{
+   "musicId": "2056228066",
+   "musicName": "Eternal regret",
+   "artist": [
+      [
+         "AnRain",
+         "35516167"
+      ]
+   ],
+   "albumId": "167614757",
+   "album": "Eternal regret",
+   "albumPicDocId": "109951168681303606",
+   "albumPic": "http://p4.music.126.net/XfUBgbH9RDQqBcStBVa-Pw==/109951168681303606.jpg",
+   "bitrate": 320000,
+   "mp3DocId": "d27e546865e86910e1da2ccbefd00a15",
+   "duration": 186741,
+   "mvId": "",
+   "alias": [],
+   "transNames": [],
+   "format": "mp3",
+   "fee": 8,
+   "volumeDelta": -8.0989,
+   "privilege": {
+      "flag": 1544198
+   }
+}"#,
+        )?;
+        let info = NcmInfo::from(raw);
+        assert_eq!(info.id, 2056228066);
+        assert_eq!(info.artist, vec!((String::from("AnRain"), 35516167)));
+        assert_eq!(info.bitrate, 320000);
+        assert_eq!(info.duration, 186741);
+        assert_eq!(info.mv_id, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_decrypt() {
+        let key = [
+            0x23, 0x31, 0x34, 0x6C, 0x6A, 0x6B, 0x5F, 0x21, 0x5C, 0x5D, 0x26, 0x30, 0x55, 0x3C,
+            0x27, 0x28,
+        ];
+        let source = [
+            0x2F, 0xAE, 0x05, 0x53, 0x75, 0xB8, 0x63, 0x15, 0xF7, 0x64, 0x54, 0xE6, 0x6D, 0x9F,
+            0xBC, 0xFD, 0xD4, 0xC3, 0xE5, 0x4A, 0x5D, 0x7D, 0x61, 0x47, 0x93, 0x82, 0x00, 0xAC,
+            0x48, 0xE4, 0x2C, 0x96, 0x43, 0x0F, 0x53, 0x54, 0xBE, 0x36, 0xC8, 0x6D, 0xE5, 0x46,
+            0x47, 0x7E, 0x87, 0x88, 0xBC, 0xF6, 0x0F, 0x97, 0x0F, 0xA9, 0x60, 0xDB, 0xF7, 0x4C,
+            0xD1, 0xD0, 0xD4, 0x44, 0xEC, 0xF6, 0x89, 0x44, 0x6B, 0x88, 0xD3, 0x2A, 0x39, 0x25,
+            0xB8, 0x09, 0x71, 0xA9, 0x3F, 0xE0, 0xC1, 0x07, 0x22, 0x3A, 0xB6, 0x0B, 0xF0, 0xA1,
+            0xB3, 0x26, 0x96, 0x62, 0xBD, 0x30, 0x76, 0xB5, 0xDA, 0x03, 0x6B, 0xB0, 0x99, 0x43,
+            0x2E, 0x7B, 0x8F, 0xD4, 0xD2, 0x9E, 0xFF, 0x37, 0x24, 0xB5, 0x25, 0x56, 0x6A, 0x0B,
+            0x2B, 0x41, 0x19, 0x52, 0xF6, 0xC8, 0x6E, 0x56, 0xE8, 0xC4, 0xF0, 0xCA, 0xFC, 0x9F,
+            0x09, 0xA4, 0xED, 0x51, 0x94, 0x7E, 0xDD, 0xE4, 0xF1, 0x3D, 0x63, 0xEB, 0x7A, 0xBB,
+            0x8C, 0xE6, 0x8F, 0x42, 0xDA, 0xE1, 0x7F, 0x17, 0xB5, 0x06, 0xC8, 0x34, 0x1C, 0xD7,
+            0x4C, 0x7F, 0xBE, 0x7B, 0xE8, 0x66, 0xB1, 0x0C, 0xB6, 0x57, 0x65, 0x78, 0xCF, 0xD0,
+            0xBA, 0xCC, 0x78, 0x09, 0xC1, 0x0F, 0x50, 0xDE, 0x1A, 0x2A, 0x27, 0x5D, 0x83, 0x12,
+            0xBB, 0x3A, 0xA5, 0x12, 0xFB, 0x54, 0xA2, 0xC9, 0x4D, 0x15, 0x46, 0x9F, 0x36, 0x8D,
+            0xF6, 0x79, 0x98, 0xA7, 0x34, 0x7F, 0x84, 0x6D, 0xD9, 0xC7, 0x3C, 0x51, 0xA6, 0x8B,
+            0x95, 0x42, 0x09, 0x13, 0xA9, 0xAE, 0x2C, 0xE4, 0x5F, 0x8A, 0x26, 0x67, 0x3B, 0xF5,
+            0x01, 0x36, 0x0D, 0x20, 0xD0, 0x2C, 0xDE, 0xA1, 0xE2, 0x03, 0x62, 0xD3, 0xB8, 0x9F,
+            0x65, 0xE8, 0xD7, 0xC1, 0x05, 0x88, 0x83, 0x68, 0x50, 0xDB, 0xC7, 0x0F, 0xE5, 0x79,
+            0xF7, 0x3F, 0x37, 0x4A, 0xF4, 0xD9, 0x82, 0xB1, 0xB3, 0x04, 0x0B, 0xB0, 0xD8, 0x5C,
+            0xFA, 0x03, 0x9C, 0x63, 0xFF, 0xCA, 0xE2, 0xE4, 0x57, 0x08, 0x2A, 0x05, 0x34, 0x16,
+            0x03, 0xED, 0xC7, 0x85, 0xE0, 0x3C, 0x43, 0x53, 0x78, 0x8C, 0x88, 0x7F, 0x52, 0xE6,
+            0x94, 0x5D, 0xC7, 0x9A, 0x21, 0xD0, 0xEA, 0x89, 0x7B, 0x09, 0xB0, 0xDA, 0xA2, 0x0B,
+            0xEE, 0xC2, 0x66, 0x04, 0x2A, 0xA0, 0x9C, 0x28, 0xDC, 0xA9, 0xB4, 0x71, 0x90, 0xC8,
+            0x9B, 0x00, 0x7B, 0xF5, 0x7A, 0x9C, 0xAF, 0x2B, 0x8A, 0x7B, 0x0F, 0x70, 0x7F, 0x44,
+            0x01, 0x5A, 0xDB, 0x6D, 0x8E, 0x98, 0x3D, 0x4E, 0x14, 0x71, 0xC1, 0xB2, 0x0F, 0x66,
+            0x8F, 0x14, 0x0A, 0x5C, 0x78, 0xE9, 0x16, 0xD2, 0x45, 0x4F, 0x1F, 0xE3, 0x3F, 0x0E,
+            0xBD, 0x5D, 0x40, 0x75,
+        ];
+        let target = [
+            0x6D, 0x75, 0x73, 0x69, 0x63, 0x3A, 0x7B, 0x22, 0x6D, 0x75, 0x73, 0x69, 0x63, 0x49,
+            0x64, 0x22, 0x3A, 0x31, 0x33, 0x30, 0x35, 0x33, 0x36, 0x36, 0x35, 0x35, 0x36, 0x2C,
+            0x22, 0x6D, 0x75, 0x73, 0x69, 0x63, 0x4E, 0x61, 0x6D, 0x65, 0x22, 0x3A, 0x22, 0xE5,
+            0xAF, 0x92, 0xE9, 0xB8, 0xA6, 0xE5, 0xB0, 0x91, 0xE5, 0xB9, 0xB4, 0x22, 0x2C, 0x22,
+            0x61, 0x6C, 0x69, 0x61, 0x73, 0x22, 0x3A, 0x5B, 0x22, 0xE7, 0x94, 0xB5, 0xE8, 0xA7,
+            0x86, 0xE5, 0x89, 0xA7, 0xE3, 0x80, 0x8A, 0xE6, 0x96, 0x97, 0xE7, 0xA0, 0xB4, 0xE8,
+            0x8B, 0x8D, 0xE7, 0xA9, 0xB9, 0xE3, 0x80, 0x8B, 0xE4, 0xB8, 0xBB, 0xE9, 0xA2, 0x98,
+            0xE6, 0x9B, 0xB2, 0x22, 0x5D, 0x2C, 0x22, 0x61, 0x72, 0x74, 0x69, 0x73, 0x74, 0x22,
+            0x3A, 0x5B, 0x5B, 0x22, 0xE5, 0x8D, 0x8E, 0xE6, 0x99, 0xA8, 0xE5, 0xAE, 0x87, 0x22,
+            0x2C, 0x38, 0x36, 0x31, 0x37, 0x37, 0x37, 0x5D, 0x5D, 0x2C, 0x22, 0x61, 0x6C, 0x62,
+            0x75, 0x6D, 0x49, 0x64, 0x22, 0x3A, 0x37, 0x32, 0x37, 0x30, 0x36, 0x37, 0x38, 0x38,
+            0x2C, 0x22, 0x61, 0x6C, 0x62, 0x75, 0x6D, 0x22, 0x3A, 0x22, 0xE5, 0xAF, 0x92, 0xE9,
+            0xB8, 0xA6, 0xE5, 0xB0, 0x91, 0xE5, 0xB9, 0xB4, 0x22, 0x2C, 0x22, 0x61, 0x6C, 0x62,
+            0x75, 0x6D, 0x50, 0x69, 0x63, 0x44, 0x6F, 0x63, 0x49, 0x64, 0x22, 0x3A, 0x31, 0x30,
+            0x39, 0x39, 0x35, 0x31, 0x31, 0x36, 0x33, 0x35, 0x32, 0x30, 0x36, 0x33, 0x38, 0x35,
+            0x32, 0x32, 0x2C, 0x22, 0x61, 0x6C, 0x62, 0x75, 0x6D, 0x50, 0x69, 0x63, 0x22, 0x3A,
+            0x22, 0x68, 0x74, 0x74, 0x70, 0x3A, 0x2F, 0x2F, 0x70, 0x33, 0x2E, 0x6D, 0x75, 0x73,
+            0x69, 0x63, 0x2E, 0x31, 0x32, 0x36, 0x2E, 0x6E, 0x65, 0x74, 0x2F, 0x71, 0x52, 0x51,
+            0x54, 0x53, 0x5F, 0x54, 0x72, 0x6F, 0x5A, 0x6F, 0x39, 0x53, 0x4C, 0x56, 0x35, 0x79,
+            0x71, 0x70, 0x54, 0x35, 0x41, 0x3D, 0x3D, 0x2F, 0x31, 0x30, 0x39, 0x39, 0x35, 0x31,
+            0x31, 0x36, 0x33, 0x35, 0x32, 0x30, 0x36, 0x33, 0x38, 0x35, 0x32, 0x32, 0x2E, 0x6A,
+            0x70, 0x67, 0x22, 0x2C, 0x22, 0x6D, 0x76, 0x49, 0x64, 0x22, 0x3A, 0x30, 0x2C, 0x22,
+            0x62, 0x69, 0x74, 0x72, 0x61, 0x74, 0x65, 0x22, 0x3A, 0x39, 0x32, 0x33, 0x33, 0x37,
+            0x38, 0x2C, 0x22, 0x64, 0x75, 0x72, 0x61, 0x74, 0x69, 0x6F, 0x6E, 0x22, 0x3A, 0x33,
+            0x31, 0x35, 0x31, 0x34, 0x36, 0x2C, 0x22, 0x66, 0x6F, 0x72, 0x6D, 0x61, 0x74, 0x22,
+            0x3A, 0x22, 0x66, 0x6C, 0x61, 0x63, 0x22, 0x7D,
+        ];
+        let result = decrypt(&source, &key).unwrap();
+        assert_eq!(&result[..], &target);
+    }
+
+    #[test]
+    fn test_build_key_box() {
+        let key = [
+            0x31, 0x31, 0x38, 0x31, 0x39, 0x38, 0x30, 0x33, 0x33, 0x32, 0x38, 0x35, 0x45, 0x37,
+            0x66, 0x54, 0x34, 0x39, 0x78, 0x37, 0x64, 0x6F, 0x66, 0x39, 0x4F, 0x4B, 0x43, 0x67,
+            0x67, 0x39, 0x63, 0x64, 0x76, 0x68, 0x45, 0x75, 0x65, 0x7A, 0x79, 0x33, 0x69, 0x5A,
+            0x43, 0x4C, 0x31, 0x6E, 0x46, 0x76, 0x42, 0x46, 0x64, 0x31, 0x54, 0x34, 0x75, 0x53,
+            0x6B, 0x74, 0x41, 0x4A, 0x4B, 0x6D, 0x77, 0x5A, 0x58, 0x73, 0x69, 0x6A, 0x50, 0x62,
+            0x69, 0x6A, 0x6C, 0x69, 0x69, 0x6F, 0x6E, 0x56, 0x55, 0x58, 0x58, 0x67, 0x39, 0x70,
+            0x6C, 0x54, 0x62, 0x58, 0x45, 0x63, 0x6C, 0x41, 0x45, 0x39, 0x4C, 0x62,
+        ];
+        let key_box = [
+            0x43, 0x63, 0x9D, 0xE2, 0x5B, 0x4B, 0x55, 0xBB, 0x4C, 0xCF, 0x2A, 0x62, 0x0E, 0x48,
+            0x8A, 0x15, 0x59, 0x52, 0xBA, 0x6C, 0xEF, 0x6D, 0x72, 0x39, 0xA0, 0x9A, 0xA9, 0x27,
+            0x66, 0xBC, 0xF9, 0xC0, 0x47, 0xDF, 0x7D, 0xDE, 0x3B, 0x81, 0x04, 0xFF, 0x90, 0x77,
+            0x80, 0x50, 0x54, 0xBD, 0x0D, 0x58, 0x34, 0x0A, 0x44, 0xA8, 0x5F, 0x99, 0xC6, 0xBE,
+            0x4E, 0x4D, 0x13, 0x17, 0x83, 0x01, 0x35, 0x5C, 0xF4, 0x7B, 0x53, 0x31, 0x86, 0xD4,
+            0xB8, 0xAB, 0xD1, 0xB5, 0x68, 0xDC, 0x96, 0xF1, 0x9C, 0xE8, 0x7A, 0x1B, 0xB0, 0x56,
+            0x22, 0x1A, 0x51, 0x92, 0xBF, 0xFA, 0xB1, 0x19, 0x88, 0x26, 0x49, 0x08, 0xEB, 0xAC,
+            0x14, 0x28, 0xAD, 0x3A, 0x8C, 0x85, 0x84, 0x2C, 0x82, 0xB3, 0xA6, 0xA2, 0xA3, 0x12,
+            0x78, 0xA1, 0x57, 0xAE, 0x00, 0x2F, 0xB6, 0x61, 0xA5, 0x6F, 0x5A, 0x89, 0x29, 0x46,
+            0x2E, 0x4F, 0x36, 0x40, 0x07, 0x87, 0xA7, 0x65, 0x73, 0xC4, 0x7C, 0x33, 0x1E, 0xE5,
+            0x10, 0xB4, 0xFD, 0xC9, 0xE0, 0xB7, 0x97, 0x32, 0x5D, 0x64, 0x41, 0xF0, 0x20, 0xC3,
+            0x95, 0xFE, 0xD2, 0x21, 0xFB, 0x75, 0x3D, 0x0B, 0x3E, 0xF2, 0xD5, 0xCB, 0xD6, 0xF7,
+            0x1F, 0x24, 0x45, 0x69, 0xB9, 0xDA, 0x6A, 0x76, 0x03, 0xF8, 0x70, 0x8E, 0xC1, 0xC8,
+            0xD7, 0x4A, 0xD0, 0x9E, 0xCD, 0xA4, 0xCE, 0xAA, 0x1D, 0xED, 0xF6, 0x02, 0x60, 0xE3,
+            0xDB, 0x8D, 0x09, 0xF3, 0x37, 0xE1, 0xC5, 0xCA, 0x8F, 0x2D, 0x7F, 0x74, 0x42, 0x6E,
+            0x8B, 0x3F, 0x23, 0xC2, 0xD3, 0xCC, 0xD9, 0xEE, 0x98, 0xE6, 0x11, 0x05, 0xEA, 0xD8,
+            0xB2, 0xE4, 0xF5, 0xE7, 0x71, 0x2B, 0x93, 0x9B, 0x3C, 0x30, 0xE9, 0xC7, 0x38, 0xEC,
+            0x18, 0x6B, 0x79, 0xFC, 0xAF, 0x5E, 0x9F, 0x7E, 0x91, 0xDD, 0x16, 0x94, 0x0F, 0x06,
+            0x67, 0x25, 0x0C, 0x1C,
+        ];
+        assert_eq!(build_key_box(&key), key_box);
+    }
+}
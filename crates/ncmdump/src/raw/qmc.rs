@@ -0,0 +1,257 @@
+//! The ciphers and the sans-IO parser of qmc file.
+
+use crate::error::Result;
+use crate::raw::qmc::map::MapCipher;
+use crate::raw::qmc::rc4::Rc4Cipher;
+use crate::raw::Event;
+
+mod map;
+mod rc4;
+
+const MAP_KEY_MAX_LENGTH: usize = 300;
+
+const KEY: [u8; 256] = [
+    0x77, 0x48, 0x32, 0x73, 0xDE, 0xF2, 0xC0, 0xC8, 0x95, 0xEC, 0x30, 0xB2, 0x51, 0xC3, 0xE1, 0xA0,
+    0x9E, 0xE6, 0x9D, 0xCF, 0xFA, 0x7F, 0x14, 0xD1, 0xCE, 0xB8, 0xDC, 0xC3, 0x4A, 0x67, 0x93, 0xD6,
+    0x28, 0xC2, 0x91, 0x70, 0xCA, 0x8D, 0xA2, 0xA4, 0xF0, 0x08, 0x61, 0x90, 0x7E, 0x6F, 0xA2, 0xE0,
+    0xEB, 0xAE, 0x3E, 0xB6, 0x67, 0xC7, 0x92, 0xF4, 0x91, 0xB5, 0xF6, 0x6C, 0x5E, 0x84, 0x40, 0xF7,
+    0xF3, 0x1B, 0x02, 0x7F, 0xD5, 0xAB, 0x41, 0x89, 0x28, 0xF4, 0x25, 0xCC, 0x52, 0x11, 0xAD, 0x43,
+    0x68, 0xA6, 0x41, 0x8B, 0x84, 0xB5, 0xFF, 0x2C, 0x92, 0x4A, 0x26, 0xD8, 0x47, 0x6A, 0x7C, 0x95,
+    0x61, 0xCC, 0xE6, 0xCB, 0xBB, 0x3F, 0x47, 0x58, 0x89, 0x75, 0xC3, 0x75, 0xA1, 0xD9, 0xAF, 0xCC,
+    0x08, 0x73, 0x17, 0xDC, 0xAA, 0x9A, 0xA2, 0x16, 0x41, 0xD8, 0xA2, 0x06, 0xC6, 0x8B, 0xFC, 0x66,
+    0x34, 0x9F, 0xCF, 0x18, 0x23, 0xA0, 0x0A, 0x74, 0xE7, 0x2B, 0x27, 0x70, 0x92, 0xE9, 0xAF, 0x37,
+    0xE6, 0x8C, 0xA7, 0xBC, 0x62, 0x65, 0x9C, 0xC2, 0x08, 0xC9, 0x88, 0xB3, 0xF3, 0x43, 0xAC, 0x74,
+    0x2C, 0x0F, 0xD4, 0xAF, 0xA1, 0xC3, 0x01, 0x64, 0x95, 0x4E, 0x48, 0x9F, 0xF4, 0x35, 0x78, 0x95,
+    0x7A, 0x39, 0xD6, 0x6A, 0xA0, 0x6D, 0x40, 0xE8, 0x4F, 0xA8, 0xEF, 0x11, 0x1D, 0xF3, 0x1B, 0x3F,
+    0x3F, 0x07, 0xDD, 0x6F, 0x5B, 0x19, 0x30, 0x19, 0xFB, 0xEF, 0x0E, 0x37, 0xF0, 0x0E, 0xCD, 0x16,
+    0x49, 0xFE, 0x53, 0x47, 0x13, 0x1A, 0xBD, 0xA4, 0xF1, 0x40, 0x19, 0x60, 0x0E, 0xED, 0x68, 0x09,
+    0x06, 0x5F, 0x4D, 0xCF, 0x3D, 0x1A, 0xFE, 0x20, 0x77, 0xE4, 0xD9, 0xDA, 0xF9, 0xA4, 0x2B, 0x76,
+    0x1C, 0x71, 0xDB, 0x00, 0xBC, 0xFD, 0x0C, 0x6C, 0xA5, 0x47, 0xF7, 0xF6, 0x00, 0x79, 0x4A, 0x11,
+];
+
+/// Get the mask of the byte at the offset.
+pub fn map_l(value: u64) -> u8 {
+    let v = if value > 0x7FFF {
+        value % 0x7FFF
+    } else {
+        value
+    } as usize;
+    let index = (v * v + 80923) % 256;
+    KEY[index]
+}
+
+/// Encrypt or decrypt the music data in place, the offset is from the start of file.
+pub fn encrypt(offset: u64, buffer: &mut [u8]) {
+    for (index, byte) in buffer.iter_mut().enumerate() {
+        *byte ^= map_l(offset + index as u64);
+    }
+}
+
+/// The cipher of qmc file, the cipher of v2 file is decided by the length of key.
+pub(crate) enum Cipher {
+    Static,
+    Map(MapCipher),
+    Rc4(Rc4Cipher),
+}
+
+impl Cipher {
+    /// Create the cipher of qmc v2 file from the decrypted key.
+    pub(crate) fn new(key: &[u8]) -> Result<Self> {
+        if key.len() > MAP_KEY_MAX_LENGTH {
+            Ok(Self::Rc4(Rc4Cipher::new(key)?))
+        } else {
+            Ok(Self::Map(MapCipher::new(key)?))
+        }
+    }
+
+    pub(crate) fn encrypt(&self, offset: u64, buffer: &mut [u8]) {
+        match self {
+            Self::Static => encrypt(offset, buffer),
+            Self::Map(cipher) => cipher.encrypt(offset, buffer),
+            Self::Rc4(cipher) => cipher.encrypt(offset, buffer),
+        }
+    }
+}
+
+/// The sans-IO parser of qmc file.
+///
+/// The file has no header, so every input is returned as [`Event::Audio`].
+/// If the length of music data is known, such as from the trailer of file,
+/// the trailer after the music data is skipped.
+///
+/// # Example
+///
+/// ```rust
+/// use ncmdump::raw::qmc::Parser;
+/// use ncmdump::raw::Event;
+///
+/// let mut parser = Parser::new().with_length(4);
+/// let (size, event) = parser.parse(b"\xA5\x06\xB7\x89STag").unwrap();
+/// assert_eq!((size, event), (4, Event::Audio(b"fLaC".to_vec())));
+/// assert_eq!(parser.parse(b"STag").unwrap(), (4, Event::NeedMoreData(1)));
+/// ```
+pub struct Parser {
+    cipher: Cipher,
+    cursor: u64,
+    length: Option<u64>,
+}
+
+impl Default for Parser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Parser {
+    /// Create the parser of qmc v1 file, which uses the static cipher.
+    pub fn new() -> Self {
+        Self {
+            cipher: Cipher::Static,
+            cursor: 0,
+            length: None,
+        }
+    }
+
+    /// Create the parser of qmc v2 file with the decrypted key,
+    /// the key is usually decrypted from the ekey by `ekey::decrypt`.
+    pub fn with_key(key: &[u8]) -> Result<Self> {
+        Ok(Self {
+            cipher: Cipher::new(key)?,
+            cursor: 0,
+            length: None,
+        })
+    }
+
+    /// Set the length of music data, the data after it is the trailer.
+    pub fn with_length(mut self, length: u64) -> Self {
+        self.length = Some(length);
+        self
+    }
+
+    /// Get the length of music data, it's `None` if the length is unknown.
+    pub fn length(&self) -> Option<u64> {
+        self.length
+    }
+
+    /// Get the offset of the next input from the start of file.
+    pub fn position(&self) -> u64 {
+        self.cursor
+    }
+
+    /// Move to the offset from the start of file, it's used after seeking the reader.
+    pub fn set_position(&mut self, position: u64) {
+        self.cursor = position;
+    }
+
+    /// Get the length of music data which is not parsed yet.
+    /// It's `None` if the length is unknown.
+    pub fn remaining(&self) -> Option<u64> {
+        self.length.map(|length| length.saturating_sub(self.cursor))
+    }
+
+    /// Decrypt the music data in place, and return the length of it.
+    /// The rest of buffer is the trailer, which is left unchanged.
+    pub fn decrypt(&mut self, buffer: &mut [u8]) -> usize {
+        let size = match self.remaining() {
+            Some(remain) => buffer
+                .len()
+                .min(usize::try_from(remain).unwrap_or(usize::MAX)),
+            None => buffer.len(),
+        };
+        self.cipher.encrypt(self.cursor, &mut buffer[..size]);
+        self.cursor += size as u64;
+        size
+    }
+
+    /// Parse the input, and return the length of consumed input with the next event.
+    /// The trailer is consumed without any event.
+    pub fn parse(&mut self, input: &[u8]) -> Result<(usize, Event)> {
+        let mut data = input.to_vec();
+        let size = self.decrypt(&mut data);
+        if size == 0 {
+            return Ok((input.len(), Event::NeedMoreData(1)));
+        }
+        data.truncate(size);
+        Ok((size, Event::Audio(data)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+
+    use super::*;
+
+    #[test]
+    fn test_qmcdump_map_ok() {
+        let dest = map_l(0x99);
+        assert_eq!(dest, 146);
+
+        let dest = map_l(0x8FFF);
+        assert_eq!(dest, 195);
+    }
+
+    #[test]
+    fn test_qmcdump_encrypt_ok() {
+        let mut data = [0x00, 0x01, 0x02, 0x03];
+        encrypt(0, &mut data);
+        assert_eq!(data, [0xC3, 0x4B, 0xD4, 0xC9]);
+
+        let mut data = [0x00, 0x01, 0x02, 0x03];
+        encrypt(0x7fff, &mut data);
+        assert_eq!(data, [0x4A, 0x4B, 0xD4, 0xC9]);
+    }
+
+    #[test]
+    fn test_parser_ok() -> Result<()> {
+        let mut parser = Parser::new();
+        let (size, event) = parser.parse(&[0xA5, 0x06])?;
+        assert_eq!((size, event), (2, Event::Audio(b"fL".to_vec())));
+        let (size, event) = parser.parse(&[0xB7, 0x89])?;
+        assert_eq!((size, event), (2, Event::Audio(b"aC".to_vec())));
+        assert_eq!(parser.parse(&[])?, (0, Event::NeedMoreData(1)));
+        assert_eq!(parser.position(), 4);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parser_trailer_ok() -> Result<()> {
+        let mut parser = Parser::new().with_length(4);
+        let (size, event) = parser.parse(&[0xA5, 0x06, 0xB7])?;
+        assert_eq!((size, event), (3, Event::Audio(b"fLa".to_vec())));
+        let (size, event) = parser.parse(b"\x89STag")?;
+        assert_eq!((size, event), (1, Event::Audio(b"C".to_vec())));
+        assert_eq!(parser.remaining(), Some(0));
+        assert_eq!(parser.parse(b"STag")?, (4, Event::NeedMoreData(1)));
+
+        parser.set_position(1);
+        let mut buffer = [0x06, 0xB7, 0x89, 0x00];
+        assert_eq!(parser.decrypt(&mut buffer), 3);
+        assert_eq!(&buffer, b"LaC\x00");
+        Ok(())
+    }
+
+    #[test]
+    fn test_parser_with_key_ok() -> Result<()> {
+        let parser = Parser::with_key(b"key")?;
+        assert!(matches!(parser.cipher, Cipher::Map(_)));
+        let parser = Parser::with_key(&[0x01; MAP_KEY_MAX_LENGTH + 1])?;
+        assert!(matches!(parser.cipher, Cipher::Rc4(_)));
+        assert!(Parser::with_key(&[]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_encrypt_head_ok() -> Result<()> {
+        // fLaC
+        let mut input = [0xA5, 0x06, 0xB7, 0x89];
+        encrypt(0, &mut input);
+        assert_eq!(input, [0x66, 0x4C, 0x61, 0x43]);
+
+        // ID3
+        let mut input = [0x8A, 0x0E, 0xE5];
+        encrypt(0, &mut input);
+        assert_eq!(input, [0x49, 0x44, 0x33]);
+        Ok(())
+    }
+}
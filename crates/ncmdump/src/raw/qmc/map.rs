@@ -0,0 +1,71 @@
+use alloc::vec::Vec;
+
+use crate::error::{Errors, Result};
+
+/// The map cipher of qmc v2 file, which is used for the key not longer than 300 bytes.
+pub(crate) struct MapCipher {
+    key: Vec<u8>,
+}
+
+impl MapCipher {
+    /// Create the map cipher from the decrypted file key.
+    pub(crate) fn new(key: &[u8]) -> Result<Self> {
+        if key.is_empty() {
+            return Err(Errors::InvalidKeyLength);
+        }
+        Ok(Self { key: key.to_vec() })
+    }
+
+    fn rotate(value: u8, bits: usize) -> u8 {
+        let rotate = (bits + 4) % 8;
+        let left = (value as u32) << rotate;
+        let right = (value as u32) >> rotate;
+        (left | right) as u8
+    }
+
+    fn map_l(&self, value: u64) -> u8 {
+        let v = if value > 0x7FFF {
+            value % 0x7FFF
+        } else {
+            value
+        } as usize;
+        let index = (v * v + 71214) % self.key.len();
+        Self::rotate(self.key[index], index & 0x7)
+    }
+
+    pub(crate) fn encrypt(&self, offset: u64, buffer: &mut [u8]) {
+        for (index, byte) in buffer.iter_mut().enumerate() {
+            *byte ^= self.map_l(offset + index as u64);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_map_cipher_new_err() {
+        assert!(matches!(MapCipher::new(&[]), Err(Errors::InvalidKeyLength)));
+    }
+
+    #[test]
+    fn test_map_cipher_rotate_ok() {
+        assert_eq!(MapCipher::rotate(0x12, 0), 0x21);
+        assert_eq!(MapCipher::rotate(0x81, 1), 0x24);
+        assert_eq!(MapCipher::rotate(0x81, 4), 0x81);
+    }
+
+    #[test]
+    fn test_map_cipher_encrypt_ok() -> Result<()> {
+        let cipher = MapCipher::new(b"0123456789")?;
+        let mut data = [0x00, 0x01, 0x02, 0x03];
+        cipher.encrypt(0, &mut data);
+        assert_eq!(data, [0x34, 0x7B, 0x81, 0x83]);
+
+        let mut data = [0x00, 0x01, 0x02, 0x03];
+        cipher.encrypt(0x7FFF, &mut data);
+        assert_eq!(data, [0x80, 0x7B, 0x81, 0x83]);
+        Ok(())
+    }
+}
@@ -0,0 +1,137 @@
+use alloc::vec::Vec;
+
+use crate::error::{Errors, Result};
+
+const FIRST_SEGMENT_SIZE: u64 = 128;
+const SEGMENT_SIZE: u64 = 5120;
+
+/// The modified rc4 cipher of qmc v2 file, which is used for the key longer than 300 bytes.
+///
+/// The first 128 bytes are mixed with the key directly, and the rest is split into
+/// 5120 bytes segments, each segment restarts the rc4 stream with a skip from the key.
+pub(crate) struct Rc4Cipher {
+    key: Vec<u8>,
+    key_box: Vec<u8>,
+    hash: u32,
+}
+
+impl Rc4Cipher {
+    /// Create the rc4 cipher from the decrypted file key.
+    pub(crate) fn new(key: &[u8]) -> Result<Self> {
+        if key.is_empty() {
+            return Err(Errors::InvalidKeyLength);
+        }
+        Ok(Self {
+            key: key.to_vec(),
+            key_box: Self::build_key_box(key),
+            hash: Self::build_hash(key),
+        })
+    }
+
+    fn build_key_box(key: &[u8]) -> Vec<u8> {
+        let n = key.len();
+        let mut key_box = (0..n).map(|i| i as u8).collect::<Vec<u8>>();
+        let mut j = 0;
+        for i in 0..n {
+            j = (j + key_box[i] as usize + key[i] as usize) % n;
+            key_box.swap(i, j);
+        }
+        key_box
+    }
+
+    fn build_hash(key: &[u8]) -> u32 {
+        let mut hash = 1u32;
+        for &k in key.iter().filter(|&&k| k != 0) {
+            let next = hash.wrapping_mul(k as u32);
+            if next == 0 || next <= hash {
+                break;
+            }
+            hash = next;
+        }
+        hash
+    }
+
+    fn get_segment_skip(&self, id: u64) -> usize {
+        let n = self.key.len();
+        let seed = self.key[(id % n as u64) as usize] as f64;
+        let index = (self.hash as f64 / ((id + 1) as f64 * seed) * 100.0) as u64;
+        (index % n as u64) as usize
+    }
+
+    fn encrypt_first_segment(&self, offset: u64, buffer: &mut [u8]) {
+        for (index, byte) in buffer.iter_mut().enumerate() {
+            *byte ^= self.key[self.get_segment_skip(offset + index as u64)];
+        }
+    }
+
+    fn encrypt_segment(&self, offset: u64, buffer: &mut [u8]) {
+        let n = self.key.len();
+        let mut key_box = self.key_box.clone();
+        let (mut j, mut k) = (0, 0);
+        let skip = (offset % SEGMENT_SIZE) as usize + self.get_segment_skip(offset / SEGMENT_SIZE);
+        for i in 0..skip + buffer.len() {
+            j = (j + 1) % n;
+            k = (key_box[j] as usize + k) % n;
+            key_box.swap(j, k);
+            if i >= skip {
+                buffer[i - skip] ^= key_box[(key_box[j] as usize + key_box[k] as usize) % n];
+            }
+        }
+    }
+
+    pub(crate) fn encrypt(&self, offset: u64, buffer: &mut [u8]) {
+        let mut offset = offset;
+        let mut buffer = buffer;
+
+        if offset < FIRST_SEGMENT_SIZE {
+            let size = buffer.len().min((FIRST_SEGMENT_SIZE - offset) as usize);
+            let (head, tail) = buffer.split_at_mut(size);
+            self.encrypt_first_segment(offset, head);
+            offset += size as u64;
+            buffer = tail;
+        }
+
+        while !buffer.is_empty() {
+            let remain = SEGMENT_SIZE - offset % SEGMENT_SIZE;
+            let size = buffer.len().min(remain as usize);
+            let (head, tail) = buffer.split_at_mut(size);
+            self.encrypt_segment(offset, head);
+            offset += size as u64;
+            buffer = tail;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rc4_cipher_new_err() {
+        assert!(matches!(Rc4Cipher::new(&[]), Err(Errors::InvalidKeyLength)));
+    }
+
+    #[test]
+    fn test_rc4_cipher_build_hash_ok() {
+        assert_eq!(Rc4Cipher::build_hash(&[0x02, 0x00, 0x03]), 6);
+        assert_eq!(
+            Rc4Cipher::build_hash(&[0xFF, 0xFF, 0xFF, 0xFF, 0xFF]),
+            0xFC05FC01
+        );
+    }
+
+    #[test]
+    fn test_rc4_cipher_random_access_ok() -> Result<()> {
+        let key = (0..400).map(|i| (i * 7 + 1) as u8).collect::<Vec<u8>>();
+        let cipher = Rc4Cipher::new(&key)?;
+
+        let mut whole = vec![0; 12000];
+        cipher.encrypt(0, &mut whole);
+        for (start, end) in [(0, 200), (100, 5200), (5119, 5121), (10000, 12000)] {
+            let mut part = vec![0; end - start];
+            cipher.encrypt(start as u64, &mut part);
+            assert_eq!(part, whole[start..end]);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,345 @@
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+use crate::dump::Dump;
+use crate::error::{Errors, Result};
+use crate::utils::{Detection, FileType};
+
+/// The length of head which is passed to the magic matcher.
+pub const HEAD_SIZE: usize = 0x100;
+
+/// The reader which is passed to the constructor of format.
+pub trait ReadSeek: Read + Seek {}
+
+impl<T> ReadSeek for T where T: Read + Seek {}
+
+type Matcher<T> = Box<dyn Fn(&T) -> bool + Send + Sync>;
+type Constructor = Box<dyn Fn(Box<dyn ReadSeek>) -> Result<Box<dyn Dump>> + Send + Sync>;
+
+/// The format which can be registered in the [`Registry`].
+///
+/// # Example
+///
+/// ```rust
+/// use ncmdump::{Format, QmcDump};
+///
+/// let format = Format::new("qmcflac", |reader| {
+///     Ok(Box::new(QmcDump::from_seekable_reader(reader)?))
+/// })
+/// .with_magic(&[0xA5, 0x06, 0xB7, 0x89])
+/// .with_extensions(&["qmcflac"]);
+/// assert!(format.is_extension_match("QMCFLAC"));
+/// ```
+pub struct Format {
+    name: String,
+    magic: Option<Matcher<[u8]>>,
+    extension: Option<Matcher<str>>,
+    constructor: Constructor,
+}
+
+impl Format {
+    /// Create the format with the name and the constructor of dumper.
+    /// The format without any matcher can only be opened by the name.
+    pub fn new<F>(name: &str, constructor: F) -> Self
+    where
+        F: Fn(Box<dyn ReadSeek>) -> Result<Box<dyn Dump>> + Send + Sync + 'static,
+    {
+        Self {
+            name: name.to_string(),
+            magic: None,
+            extension: None,
+            constructor: Box::new(constructor),
+        }
+    }
+
+    /// Create the format of built-in file type, it's named as the lowercase of file type.
+    ///
+    /// The kgg and joox file need the key, the constructor should be replaced by
+    /// [`Format::with_constructor`] before opening them.
+    pub fn builtin(file_type: FileType) -> Self {
+        let name = format!("{:?}", file_type).to_ascii_lowercase();
+        let magic_type = file_type.clone();
+        let extension_type = file_type.clone();
+        Self::new(&name, move |reader| {
+            crate::open_as(reader, file_type.clone())
+        })
+        .with_magic_matcher(move |head| {
+            Detection::sniff(head, None).is_some_and(|detection| detection.file_type == magic_type)
+        })
+        .with_extension_matcher(move |extension| {
+            FileType::from_extension(extension) == extension_type
+        })
+    }
+
+    /// Match the file which starts with the magic header.
+    pub fn with_magic(self, magic: &[u8]) -> Self {
+        let magic = magic.to_vec();
+        self.with_magic_matcher(move |head| head.starts_with(&magic))
+    }
+
+    /// Match the file by the head of file, which is at most [`HEAD_SIZE`] bytes.
+    pub fn with_magic_matcher<F>(mut self, matcher: F) -> Self
+    where
+        F: Fn(&[u8]) -> bool + Send + Sync + 'static,
+    {
+        self.magic = Some(Box::new(matcher));
+        self
+    }
+
+    /// Match the file by the extensions, which are case-insensitive.
+    pub fn with_extensions(self, extensions: &[&str]) -> Self {
+        let extensions = extensions
+            .iter()
+            .map(|extension| extension.to_ascii_lowercase())
+            .collect::<Vec<String>>();
+        self.with_extension_matcher(move |extension| {
+            extensions.contains(&extension.to_ascii_lowercase())
+        })
+    }
+
+    /// Match the file by the extension, which is without the dot.
+    pub fn with_extension_matcher<F>(mut self, matcher: F) -> Self
+    where
+        F: Fn(&str) -> bool + Send + Sync + 'static,
+    {
+        self.extension = Some(Box::new(matcher));
+        self
+    }
+
+    /// Replace the constructor of dumper, the matchers are kept.
+    pub fn with_constructor<F>(mut self, constructor: F) -> Self
+    where
+        F: Fn(Box<dyn ReadSeek>) -> Result<Box<dyn Dump>> + Send + Sync + 'static,
+    {
+        self.constructor = Box::new(constructor);
+        self
+    }
+
+    /// Get the name of format.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Check if the head of file matches the format.
+    pub fn is_magic_match(&self, head: &[u8]) -> bool {
+        self.magic.as_ref().is_some_and(|matcher| matcher(head))
+    }
+
+    /// Check if the extension of file matches the format.
+    pub fn is_extension_match(&self, extension: &str) -> bool {
+        self.extension
+            .as_ref()
+            .is_some_and(|matcher| matcher(extension))
+    }
+
+    /// Create the dumper of this format from reader.
+    pub fn open<R>(&self, reader: R) -> Result<Box<dyn Dump>>
+    where
+        R: Read + Seek + 'static,
+    {
+        (self.constructor)(Box::new(reader))
+    }
+}
+
+/// The registry of formats, the format is detected by the magic header first,
+/// and then by the extension. The format registered later takes precedence.
+///
+/// # Example
+///
+/// ```rust
+/// use std::io::Read;
+///
+/// use anyhow::Result;
+/// use ncmdump::{Format, QmcV2Dump, Registry};
+///
+/// fn main() -> Result<()> {
+///     let mut registry = Registry::builtin();
+///     registry.register(
+///         Format::new("mflac", |reader| Ok(Box::new(QmcV2Dump::from_reader(reader)?)))
+///             .with_extensions(&["mflac"]),
+///     );
+///
+///     let mut dump = registry.open_path("res/test.mflac")?;
+///     let mut music = Vec::new();
+///     dump.read_to_end(&mut music)?;
+///     Ok(())
+/// }
+/// ```
+#[derive(Default)]
+pub struct Registry {
+    formats: Vec<Format>,
+}
+
+impl Registry {
+    /// Create the empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create the registry with every built-in format.
+    pub fn builtin() -> Self {
+        let mut registry = Self::new();
+        let file_types = [
+            #[cfg(feature = "ncmdump")]
+            FileType::Ncm,
+            #[cfg(feature = "ncmdump")]
+            FileType::Uc,
+            #[cfg(feature = "qmcdump")]
+            FileType::Qmc,
+            #[cfg(feature = "qmcdump")]
+            FileType::QmcV2,
+            #[cfg(feature = "qmcdump")]
+            FileType::Tm,
+            #[cfg(feature = "kgmdump")]
+            FileType::Kgm,
+            #[cfg(feature = "kggdump")]
+            FileType::Kgg,
+            #[cfg(feature = "kwmdump")]
+            FileType::Kwm,
+            #[cfg(feature = "xmdump")]
+            FileType::Xm,
+            #[cfg(feature = "xmlydump")]
+            FileType::X2m,
+            #[cfg(feature = "xmlydump")]
+            FileType::X3m,
+            #[cfg(feature = "jooxdump")]
+            FileType::Joox,
+        ];
+        for file_type in file_types {
+            registry.register(Format::builtin(file_type));
+        }
+        registry
+    }
+
+    /// Register the format, the format with the same name is replaced.
+    pub fn register(&mut self, format: Format) {
+        match self.formats.iter_mut().find(|f| f.name == format.name) {
+            Some(f) => *f = format,
+            None => self.formats.push(format),
+        }
+    }
+
+    /// Get the format by name.
+    pub fn get(&self, name: &str) -> Option<&Format> {
+        self.formats.iter().find(|format| format.name == name)
+    }
+
+    /// Detect the format of reader, the position of reader is restored after detection.
+    pub fn detect<R>(&self, reader: &mut R, extension: Option<&str>) -> Result<Option<&Format>>
+    where
+        R: Read + Seek,
+    {
+        let position = reader.stream_position()?;
+        let mut head = Vec::with_capacity(HEAD_SIZE);
+        let result = Read::take(&mut *reader, HEAD_SIZE as u64).read_to_end(&mut head);
+        reader.seek(SeekFrom::Start(position))?;
+        result?;
+
+        let format = self
+            .formats
+            .iter()
+            .rev()
+            .find(|format| format.is_magic_match(&head))
+            .or_else(|| {
+                let extension = extension?;
+                self.formats
+                    .iter()
+                    .rev()
+                    .find(|format| format.is_extension_match(extension))
+            });
+        Ok(format)
+    }
+
+    /// Detect the format of reader and create the dumper.
+    pub fn open<R>(&self, mut reader: R, extension: Option<&str>) -> Result<Box<dyn Dump>>
+    where
+        R: Read + Seek + 'static,
+    {
+        let format = self
+            .detect(&mut reader, extension)?
+            .ok_or(Errors::InvalidFileType)?;
+        format.open(reader)
+    }
+
+    /// Detect the format of file by the magic header and the extension, and create the dumper.
+    pub fn open_path<P>(&self, path: P) -> Result<Box<dyn Dump>>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let file = File::open(path)?;
+        self.open(file, path.extension().and_then(|ext| ext.to_str()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use anyhow::Result;
+
+    use super::*;
+
+    #[cfg(feature = "ncmdump")]
+    #[test]
+    fn test_registry_builtin_ok() -> Result<()> {
+        let registry = Registry::builtin();
+        let mut file = File::open("res/test.ncm")?;
+        let format = registry.detect(&mut file, None)?.map(Format::name);
+        assert_eq!(format, Some("ncm"));
+        assert_eq!(file.stream_position()?, 0);
+
+        let mut file = File::open("res/test.uc")?;
+        let format = registry.detect(&mut file, Some("uc!"))?.map(Format::name);
+        assert_eq!(format, Some("uc"));
+        Ok(())
+    }
+
+    #[cfg(feature = "qmcdump")]
+    #[test]
+    fn test_registry_register_ok() -> Result<()> {
+        let mut registry = Registry::new();
+        registry.register(
+            Format::new("custom", |reader| {
+                Ok(Box::new(crate::QmcDump::from_seekable_reader(reader)?))
+            })
+            .with_magic(&[0xA5, 0x06])
+            .with_extensions(&["custom"]),
+        );
+
+        let mut dump = registry.open(File::open("res/test.qmcflac")?, None)?;
+        let mut buf = [0; 4];
+        dump.read_exact(&mut buf)?;
+        assert_eq!(&buf, b"fLaC");
+
+        let format = registry.detect(&mut Cursor::new([0; 8]), Some("CUSTOM"))?;
+        assert!(format.is_some());
+        let format = registry.detect(&mut Cursor::new([0; 8]), Some("qmcflac"))?;
+        assert!(format.is_none());
+        Ok(())
+    }
+
+    #[cfg(feature = "kggdump")]
+    #[test]
+    fn test_registry_replace_ok() -> Result<()> {
+        let mut registry = Registry::builtin();
+        let result = registry.open_path("res/test.kgg");
+        assert!(matches!(result, Err(Errors::KeyNotFound)));
+
+        let keys = crate::load_kgg_keys(File::open("res/KGMusicV3.json")?)?;
+        registry.register(
+            Format::builtin(FileType::Kgg).with_constructor(move |reader| {
+                Ok(Box::new(crate::KggDump::from_reader(reader, &keys)?))
+            }),
+        );
+        assert!(registry.open_path("res/test.kgg").is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn test_registry_err() {
+        let result = Registry::new().open(Cursor::new([0; 16]), Some("ncm"));
+        assert!(matches!(result, Err(Errors::InvalidFileType)));
+    }
+}
@@ -1,5 +1,6 @@
-use std::io::Read;
+use std::io::{BufRead, Read, Seek, SeekFrom};
 
+use crate::audio::AudioFormat;
 use crate::error::Result;
 
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -7,17 +8,48 @@ pub enum FileType {
     /// The standard ncm file.
     #[cfg(feature = "ncmdump")]
     Ncm,
+    /// The uc cache file of netease cloud music.
+    #[cfg(feature = "ncmdump")]
+    Uc,
     /// The standard qmc file.
     #[cfg(feature = "qmcdump")]
     Qmc,
+    /// The qmc v2 file, which has the key at the end of file.
+    #[cfg(feature = "qmcdump")]
+    QmcV2,
+    /// The qq music cache file, like `.tm2` and `.tm6` file.
+    #[cfg(feature = "qmcdump")]
+    Tm,
+    /// The kgm file, it's also used for the vpr file.
+    #[cfg(feature = "kgmdump")]
+    Kgm,
+    /// The kgg file, which shares the magic header with the kgm file.
+    #[cfg(feature = "kggdump")]
+    Kgg,
+    /// The kwm file.
+    #[cfg(feature = "kwmdump")]
+    Kwm,
+    /// The xm file.
+    #[cfg(feature = "xmdump")]
+    Xm,
+    /// The x2m file of ximalaya.
+    #[cfg(feature = "xmlydump")]
+    X2m,
+    /// The x3m file of ximalaya.
+    #[cfg(feature = "xmlydump")]
+    X3m,
+    /// The joox v4 file, like `.ofl_en` file.
+    #[cfg(feature = "jooxdump")]
+    Joox,
     /// The other file type.
     Other,
 }
 
 impl FileType {
-    /// Return the file type of the reader.
+    /// Return the file type of the reader by the magic header.
     ///
-    /// > Notice: This function can't resolve the `NcmDump` or `QmcDump`
+    /// > Notice: This function consumes the head of reader,
+    /// > use [`Detection::from_reader`] or [`Detection::from_buf_read`] to keep it.
     ///
     /// # Example
     ///
@@ -32,21 +64,278 @@ impl FileType {
     where
         R: Read,
     {
-        let mut head = [0; 8];
-        if reader.read(&mut head)? != 8 {
-            return Ok(FileType::Other);
-        }
+        let mut head = Vec::with_capacity(8);
+        reader.take(8).read_to_end(&mut head)?;
+        let file_type = Detection::from_magic(&head)
+            .map(|detection| detection.file_type)
+            .unwrap_or(FileType::Other);
+        Ok(file_type)
+    }
+
+    /// Return the file type of the reader, the extension is used if the magic header is unknown.
+    /// The position of reader is restored after sniffing.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::fs::File;
+    /// # use ncmdump::utils::FileType;
+    /// #
+    /// let mut file = File::open("res/test.ncm").unwrap();
+    /// let file_type = FileType::detect(&mut file, Some("ncm")).unwrap();
+    /// assert_eq!(file_type, FileType::Ncm);
+    /// ```
+    pub fn detect<R>(reader: &mut R, extension: Option<&str>) -> Result<Self>
+    where
+        R: Read + Seek,
+    {
+        let file_type = Detection::from_reader(reader, extension)?
+            .map(|detection| detection.file_type)
+            .unwrap_or(FileType::Other);
+        Ok(file_type)
+    }
 
-        let file_type = match head[..] {
+    /// Return the file type by the extension of file.
+    /// It's useful for the file which has no magic header, like the qmc v2 file.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ncmdump::utils::FileType;
+    /// #
+    /// let file_type = FileType::from_extension("mflac");
+    /// ```
+    pub fn from_extension(extension: &str) -> Self {
+        match extension.to_ascii_lowercase().as_str() {
             #[cfg(feature = "ncmdump")]
-            [0x43, 0x54, 0x45, 0x4E, 0x46, 0x44, 0x41, 0x4D] => FileType::Ncm,
+            "uc" | "uc!" => FileType::Uc,
+            #[cfg(feature = "qmcdump")]
+            "mflac" | "mflac0" | "mflac1" | "mgg" | "mgg0" | "mgg1" | "mggl" => FileType::QmcV2,
             #[cfg(feature = "qmcdump")]
-            [0xA5, 0x06, 0xB7, 0x89, _, _, _, _] => FileType::Qmc,
+            "qmc0" | "qmc2" | "qmc3" | "qmcflac" | "qmcogg" => FileType::Qmc,
             #[cfg(feature = "qmcdump")]
-            [0x8A, 0x0E, 0xE5, _, _, _, _, _] => FileType::Qmc,
+            ext if ext.starts_with("bkc") => FileType::Qmc,
+            #[cfg(feature = "qmcdump")]
+            "tm0" | "tm2" | "tm3" | "tm6" => FileType::Tm,
+            #[cfg(feature = "kggdump")]
+            "kgg" => FileType::Kgg,
+            #[cfg(feature = "xmlydump")]
+            "x2m" => FileType::X2m,
+            #[cfg(feature = "xmlydump")]
+            "x3m" => FileType::X3m,
             _ => FileType::Other,
+        }
+    }
+}
+
+/// The length of head which is enough to detect the file type and its variant.
+pub const DETECT_SIZE: usize = 0x18;
+
+/// How the file type is detected, the magic header is more reliable than the extension.
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum Confidence {
+    /// Detected by the extension of file only.
+    Extension,
+    /// Detected by the magic header or the trailer of file.
+    Magic,
+}
+
+/// The result of file type detection.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Detection {
+    /// The type of file
+    pub file_type: FileType,
+    /// The format of audio in the file, if it can be told without decrypting
+    pub audio_format: Option<AudioFormat>,
+    /// The version in the header of file, such as the ncm or kgm version
+    pub version: Option<u32>,
+    /// How the file type is detected
+    pub confidence: Confidence,
+}
+
+impl Detection {
+    fn new(file_type: FileType, audio_format: Option<AudioFormat>, version: Option<u32>) -> Self {
+        Self {
+            file_type,
+            audio_format,
+            version,
+            confidence: Confidence::Magic,
+        }
+    }
+
+    /// Detect the file by the magic header.
+    pub(crate) fn from_magic(head: &[u8]) -> Option<Self> {
+        if head.len() < 8 {
+            return None;
+        }
+        #[cfg(any(feature = "kgmdump", feature = "kggdump"))]
+        let version = |start: usize| {
+            head.get(start..start + 4)
+                .map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
         };
-        Ok(file_type)
+
+        let detected: Option<(FileType, Option<AudioFormat>, Option<u32>)> = match head {
+            #[cfg(feature = "ncmdump")]
+            [0x43, 0x54, 0x45, 0x4E, 0x46, 0x44, 0x41, 0x4D, rest @ ..] => {
+                Some((FileType::Ncm, None, rest.first().map(|&v| v as u32)))
+            }
+            // The qmc file is detected by the encrypted head of audio
+            #[cfg(feature = "qmcdump")]
+            [0xA5, 0x06, 0xB7, 0x89, ..] => Some((FileType::Qmc, Some(AudioFormat::Flac), None)),
+            #[cfg(feature = "qmcdump")]
+            [0x8A, 0x0E, 0xE5, ..] => Some((FileType::Qmc, Some(AudioFormat::Mp3), None)),
+            #[cfg(feature = "qmcdump")]
+            [0x51, 0x51, 0x4D, 0x55, ..] => Some((FileType::Tm, Some(AudioFormat::M4a), None)),
+            // The kgg file shares the magic header with the kgm file, but its version is 5
+            #[cfg(feature = "kggdump")]
+            [0x7C, 0xD5, 0x32, 0xEB, 0x86, 0x02, 0x7F, 0x4B, ..] if version(0x14) == Some(5) => {
+                Some((FileType::Kgg, None, Some(5)))
+            }
+            #[cfg(feature = "kgmdump")]
+            [0x7C, 0xD5, 0x32, 0xEB, 0x86, 0x02, 0x7F, 0x4B, ..] => {
+                Some((FileType::Kgm, None, version(0x14)))
+            }
+            #[cfg(feature = "kgmdump")]
+            [0x05, 0x28, 0xBC, 0x96, 0xE9, 0xE4, 0x5A, 0x43, ..] => {
+                Some((FileType::Kgm, None, version(0x14)))
+            }
+            #[cfg(feature = "kwmdump")]
+            [0x79, 0x65, 0x65, 0x6C, 0x69, 0x6F, 0x6E, 0x2D, ..] => {
+                Some((FileType::Kwm, None, None))
+            }
+            #[cfg(feature = "xmdump")]
+            [0x69, 0x66, 0x6D, 0x74, format @ ..] => {
+                let audio_format = crate::xmdump::parse_format(&format[..4]);
+                Some((FileType::Xm, audio_format, None))
+            }
+            #[cfg(feature = "jooxdump")]
+            [0x45, 0x21, 0x30, 0x34, ..] => Some((FileType::Joox, None, Some(4))),
+            _ => None,
+        };
+        detected
+            .map(|(file_type, audio_format, version)| Self::new(file_type, audio_format, version))
+    }
+
+    /// Detect the file by the extension.
+    fn from_extension(extension: &str) -> Option<Self> {
+        let extension = extension.to_ascii_lowercase();
+        let file_type = FileType::from_extension(&extension);
+        if file_type == FileType::Other {
+            return None;
+        }
+
+        let audio_format = match extension.as_str() {
+            "qmcflac" | "mflac" | "mflac0" | "mflac1" | "bkcflac" => Some(AudioFormat::Flac),
+            "qmc0" | "qmc3" | "bkcmp3" | "tm0" | "tm3" => Some(AudioFormat::Mp3),
+            "qmc2" | "qmcogg" | "mgg" | "mgg0" | "mgg1" | "mggl" | "bkcogg" => {
+                Some(AudioFormat::Ogg)
+            }
+            "tm2" | "tm6" => Some(AudioFormat::M4a),
+            _ => None,
+        };
+        Some(Self {
+            file_type,
+            audio_format,
+            version: None,
+            confidence: Confidence::Extension,
+        })
+    }
+
+    /// Merge the detection by magic header with the extension,
+    /// the extension is only used if the magic header is unknown or has no audio format.
+    fn merge(magic: Option<Self>, extension: Option<&str>) -> Option<Self> {
+        let extension = extension.and_then(Self::from_extension);
+        match (magic, extension) {
+            (Some(mut magic), Some(extension)) if magic.file_type == extension.file_type => {
+                magic.audio_format = magic.audio_format.or(extension.audio_format);
+                Some(magic)
+            }
+            (Some(magic), _) => Some(magic),
+            (None, extension) => extension,
+        }
+    }
+
+    /// Detect the file from the head of file, the version is only found in [`DETECT_SIZE`] bytes.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ncmdump::audio::AudioFormat;
+    /// # use ncmdump::utils::{Confidence, Detection, FileType};
+    /// #
+    /// let detection = Detection::sniff(b"\x8A\x0E\xE5\x00\x00\x00\x00\x00", None).unwrap();
+    /// assert_eq!(detection.file_type, FileType::Qmc);
+    /// assert_eq!(detection.audio_format, Some(AudioFormat::Mp3));
+    /// assert_eq!(detection.confidence, Confidence::Magic);
+    /// ```
+    pub fn sniff(head: &[u8], extension: Option<&str>) -> Option<Self> {
+        Self::merge(Self::from_magic(head), extension)
+    }
+
+    /// Detect the file from a seekable reader, the position of reader is restored after detection.
+    /// The qmc v2 file is also detected by the trailer if the magic header is unknown.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::fs::File;
+    /// # use ncmdump::utils::{Detection, FileType};
+    /// #
+    /// let mut file = File::open("res/test.mflac").unwrap();
+    /// let detection = Detection::from_reader(&mut file, None).unwrap().unwrap();
+    /// assert_eq!(detection.file_type, FileType::QmcV2);
+    /// ```
+    pub fn from_reader<R>(reader: &mut R, extension: Option<&str>) -> Result<Option<Self>>
+    where
+        R: Read + Seek,
+    {
+        let position = reader.stream_position()?;
+        let magic = Self::detect_magic(reader);
+        reader.seek(SeekFrom::Start(position))?;
+        Ok(Self::merge(magic?, extension))
+    }
+
+    fn detect_magic<R>(reader: &mut R) -> Result<Option<Self>>
+    where
+        R: Read + Seek,
+    {
+        let mut head = Vec::with_capacity(DETECT_SIZE);
+        Read::take(&mut *reader, DETECT_SIZE as u64).read_to_end(&mut head)?;
+        let magic = Self::from_magic(&head);
+
+        #[cfg(feature = "qmcdump")]
+        let magic = magic.or_else(|| {
+            let trailer = crate::QmcTrailer::parse(reader).ok()?;
+            match trailer.kind {
+                crate::QmcTrailerKind::None => None,
+                _ => Some(Self::new(FileType::QmcV2, None, None)),
+            }
+        });
+        Ok(magic)
+    }
+
+    /// Detect the file from the buffer of reader without consuming it.
+    ///
+    /// Only the data already buffered is peeked, it's enough for the `BufReader`
+    /// which is just created, but the qmc v2 file can't be detected by the magic header.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::fs::File;
+    /// # use std::io::BufReader;
+    /// # use ncmdump::utils::{Detection, FileType};
+    /// #
+    /// let mut reader = BufReader::new(File::open("res/test.ncm").unwrap());
+    /// let detection = Detection::from_buf_read(&mut reader, None).unwrap().unwrap();
+    /// assert_eq!(detection.file_type, FileType::Ncm);
+    /// ```
+    pub fn from_buf_read<R>(reader: &mut R, extension: Option<&str>) -> Result<Option<Self>>
+    where
+        R: BufRead,
+    {
+        let head = reader.fill_buf()?;
+        Ok(Self::sniff(&head[..head.len().min(DETECT_SIZE)], extension))
     }
 }
 
@@ -118,9 +407,11 @@ where
 #[cfg(test)]
 mod tests {
     use std::fs::File;
-    use std::io::Error;
+    use std::io::{BufReader, Cursor, Error, Read, Seek};
 
-    use crate::utils::{is_ncm_file, FileType};
+    use crate::audio::AudioFormat;
+    use crate::error::Errors;
+    use crate::utils::{is_ncm_file, Confidence, Detection, FileType};
 
     #[cfg(feature = "ncmdump")]
     #[test]
@@ -141,4 +432,166 @@ mod tests {
         assert_eq!(file_type.unwrap(), FileType::Ncm);
         Ok(())
     }
+
+    #[cfg(feature = "qmcdump")]
+    #[test]
+    fn test_file_type_from_extension_ok() {
+        assert_eq!(FileType::from_extension("mflac"), FileType::QmcV2);
+        assert_eq!(FileType::from_extension("MGG1"), FileType::QmcV2);
+        assert_eq!(FileType::from_extension("bkcflac"), FileType::Qmc);
+        assert_eq!(FileType::from_extension("bkcmp3"), FileType::Qmc);
+        assert_eq!(FileType::from_extension("tm3"), FileType::Tm);
+        assert_eq!(FileType::from_extension("tm6"), FileType::Tm);
+        assert_eq!(FileType::from_extension("flac"), FileType::Other);
+    }
+
+    #[cfg(feature = "ncmdump")]
+    #[test]
+    fn test_file_type_from_extension_uc_ok() {
+        assert_eq!(FileType::from_extension("uc"), FileType::Uc);
+        assert_eq!(FileType::from_extension("uc!"), FileType::Uc);
+    }
+
+    #[cfg(feature = "kggdump")]
+    #[test]
+    fn test_file_type_from_extension_kgg_ok() {
+        assert_eq!(FileType::from_extension("kgg"), FileType::Kgg);
+    }
+
+    #[cfg(feature = "xmlydump")]
+    #[test]
+    fn test_file_type_from_extension_xmly_ok() {
+        assert_eq!(FileType::from_extension("x2m"), FileType::X2m);
+        assert_eq!(FileType::from_extension("X3M"), FileType::X3m);
+    }
+
+    #[cfg(feature = "qmcdump")]
+    #[test]
+    fn test_file_type_tm_ok() -> Result<(), Errors> {
+        let mut data = Cursor::new(b"QQMU\x00\x00\x00\x00");
+        assert_eq!(FileType::parse(&mut data)?, FileType::Tm);
+        Ok(())
+    }
+
+    #[cfg(feature = "jooxdump")]
+    #[test]
+    fn test_file_type_joox_ok() -> Result<(), Errors> {
+        let mut data = Cursor::new(b"E!04\x00\x00\x00\x00");
+        assert_eq!(FileType::parse(&mut data)?, FileType::Joox);
+        Ok(())
+    }
+
+    #[cfg(feature = "kgmdump")]
+    #[test]
+    fn test_file_type_kgm_ok() -> Result<(), Errors> {
+        let mut data = Cursor::new([0x7C, 0xD5, 0x32, 0xEB, 0x86, 0x02, 0x7F, 0x4B]);
+        assert_eq!(FileType::parse(&mut data)?, FileType::Kgm);
+
+        let mut data = Cursor::new([0x05, 0x28, 0xBC, 0x96, 0xE9, 0xE4, 0x5A, 0x43]);
+        assert_eq!(FileType::parse(&mut data)?, FileType::Kgm);
+        Ok(())
+    }
+
+    #[cfg(feature = "kggdump")]
+    #[test]
+    fn test_file_type_detect_ok() -> Result<(), Errors> {
+        let mut file = File::open("res/test.kgg")?;
+        assert_eq!(FileType::detect(&mut file, Some("kgg"))?, FileType::Kgg);
+        assert_eq!(FileType::detect(&mut file, None)?, FileType::Kgg);
+        assert_eq!(file.stream_position()?, 0);
+
+        let mut data = Cursor::new([0; 8]);
+        assert_eq!(FileType::detect(&mut data, Some("kgg"))?, FileType::Kgg);
+        Ok(())
+    }
+
+    #[cfg(feature = "ncmdump")]
+    #[test]
+    fn test_file_type_parse_short_read_ok() -> Result<(), Errors> {
+        let mut data = (&b"CTEN"[..]).chain(&b"FDAM"[..]);
+        assert_eq!(FileType::parse(&mut data)?, FileType::Ncm);
+        Ok(())
+    }
+
+    #[cfg(feature = "qmcdump")]
+    #[test]
+    fn test_detection_sniff_ok() {
+        let detection = Detection::sniff(b"\xA5\x06\xB7\x89\x00\x00\x00\x00", Some("qmc0"));
+        assert_eq!(
+            detection,
+            Some(Detection {
+                file_type: FileType::Qmc,
+                audio_format: Some(AudioFormat::Flac),
+                version: None,
+                confidence: Confidence::Magic,
+            })
+        );
+
+        let detection = Detection::sniff(&[0; 8], Some("mgg1"));
+        assert_eq!(
+            detection,
+            Some(Detection {
+                file_type: FileType::QmcV2,
+                audio_format: Some(AudioFormat::Ogg),
+                version: None,
+                confidence: Confidence::Extension,
+            })
+        );
+        assert_eq!(Detection::sniff(&[0; 8], Some("flac")), None);
+    }
+
+    #[cfg(all(feature = "ncmdump", feature = "kgmdump"))]
+    #[test]
+    fn test_detection_version_ok() -> Result<(), Errors> {
+        let mut file = File::open("res/test.ncm")?;
+        let detection = Detection::from_reader(&mut file, None)?.unwrap();
+        assert_eq!(detection.version, Some(1));
+
+        let mut file = File::open("res/test.kgm")?;
+        let detection = Detection::from_reader(&mut file, Some("kgg"))?.unwrap();
+        assert_eq!(detection.file_type, FileType::Kgm);
+        assert_eq!(detection.version, Some(3));
+        Ok(())
+    }
+
+    #[cfg(feature = "qmcdump")]
+    #[test]
+    fn test_detection_trailer_ok() -> Result<(), Errors> {
+        let mut file = File::open("res/test_rc4.mflac")?;
+        let detection = Detection::from_reader(&mut file, Some("mflac"))?.unwrap();
+        assert_eq!(detection.file_type, FileType::QmcV2);
+        assert_eq!(detection.audio_format, Some(AudioFormat::Flac));
+        assert_eq!(detection.confidence, Confidence::Magic);
+        assert_eq!(file.stream_position()?, 0);
+        Ok(())
+    }
+
+    #[cfg(feature = "ncmdump")]
+    #[test]
+    fn test_detection_buf_read_ok() -> Result<(), Errors> {
+        let mut reader = BufReader::new(File::open("res/test.ncm")?);
+        let detection = Detection::from_buf_read(&mut reader, None)?.unwrap();
+        assert_eq!(detection.file_type, FileType::Ncm);
+
+        let mut head = [0; 8];
+        reader.read_exact(&mut head)?;
+        assert_eq!(&head, b"CTENFDAM");
+        Ok(())
+    }
+
+    #[cfg(feature = "kwmdump")]
+    #[test]
+    fn test_file_type_kwm_ok() -> Result<(), Errors> {
+        let mut file = File::open("res/test.kwm")?;
+        assert_eq!(FileType::parse(&mut file)?, FileType::Kwm);
+        Ok(())
+    }
+
+    #[cfg(feature = "xmdump")]
+    #[test]
+    fn test_file_type_xm_ok() -> Result<(), Errors> {
+        let mut file = File::open("res/test.xm")?;
+        assert_eq!(FileType::parse(&mut file)?, FileType::Xm);
+        Ok(())
+    }
 }
@@ -0,0 +1,227 @@
+use std::io::{Read, Seek, SeekFrom, Write};
+
+use crate::audio::AudioFormat;
+use crate::dump::Dump;
+use crate::error::{Errors, Result};
+
+const BUFFER_SIZE: usize = 8192;
+const HEADER_SIZE: usize = 0x10;
+
+/// Get the format of audio by the type in header, it's also used to detect the file.
+pub(crate) fn parse_format(buffer: &[u8]) -> Option<AudioFormat> {
+    match buffer {
+        b" MP3" => Some(AudioFormat::Mp3),
+        b" A4M" => Some(AudioFormat::M4a),
+        b" WAV" => Some(AudioFormat::Wav),
+        b"FLAC" => Some(AudioFormat::Flac),
+        _ => None,
+    }
+}
+
+/// The xm file dump wrapper.
+pub struct XmDump<S>
+where
+    S: Read,
+{
+    reader: S,
+    cursor: u64,
+    format: AudioFormat,
+    start: u64,
+    key: u8,
+}
+
+impl<S> XmDump<S>
+where
+    S: Read,
+{
+    fn encrypt(&self, offset: u64, buffer: &mut [u8]) {
+        for (index, byte) in buffer.iter_mut().enumerate() {
+            if offset + index as u64 >= self.start {
+                *byte = !byte.wrapping_sub(self.key);
+            }
+        }
+    }
+
+    /// Create XmDump from reader.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use std::fs::File;
+    /// #
+    /// # use ncmdump::XmDump;
+    /// #
+    /// let file = File::open("res/test.xm").expect("Can't open file");
+    /// let _ = XmDump::from_reader(file).unwrap();
+    /// ```
+    pub fn from_reader(mut reader: S) -> Result<Self> {
+        let mut header = [0; HEADER_SIZE];
+        reader
+            .read_exact(&mut header)
+            .map_err(|_| Errors::InvalidFileType)?;
+        if &header[..4] != b"ifmt" || header[8..12] != [0xFE, 0xFE, 0xFE, 0xFE] {
+            return Err(Errors::InvalidFileType);
+        }
+
+        let format = parse_format(&header[4..8]).ok_or(Errors::InvalidFileType)?;
+        let start = u32::from_le_bytes([header[12], header[13], header[14], 0]) as u64;
+        Ok(Self {
+            reader,
+            cursor: 0,
+            format,
+            start,
+            key: header[15],
+        })
+    }
+
+    /// Get the format of music, which is recorded in the header.
+    /// It's the extension of file, like `mp3`, `m4a`, `wav` or `flac`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use std::fs::File;
+    /// #
+    /// # use ncmdump::XmDump;
+    /// #
+    /// let file = File::open("res/test.xm").expect("Can't open file");
+    /// let xm = XmDump::from_reader(file).unwrap();
+    /// assert_eq!(xm.get_format(), "flac");
+    /// ```
+    pub fn get_format(&self) -> &'static str {
+        self.format.extension()
+    }
+
+    /// Get the music data from xmdump.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use std::fs::File;
+    /// use std::io::Write;
+    ///
+    /// use anyhow::Result;
+    /// use ncmdump::XmDump;
+    ///
+    /// fn main() -> Result<()> {
+    ///     let file = File::open("res/test.xm")?;
+    ///     let mut xm = XmDump::from_reader(file)?;
+    ///     let music = xm.get_data()?;
+    ///
+    ///     let mut target = File::options()
+    ///         .create(true)
+    ///         .write(true)
+    ///         .open(format!("res/test.{}", xm.get_format()))?;
+    ///     target.write_all(&music)?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn get_data(&mut self) -> std::io::Result<Vec<u8>> {
+        let mut buffer = [0; BUFFER_SIZE];
+        let mut output = Vec::new();
+        loop {
+            let size = self.read(&mut buffer)?;
+            if size == 0 {
+                break;
+            }
+            output.write_all(&buffer[..size])?;
+        }
+        Ok(output)
+    }
+}
+
+impl<R> Read for XmDump<R>
+where
+    R: Read,
+{
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let size = self.reader.read(buf)?;
+        self.encrypt(self.cursor, &mut buf[..size]);
+        self.cursor += size as u64;
+        Ok(size)
+    }
+}
+
+impl<R> Seek for XmDump<R>
+where
+    R: Read + Seek,
+{
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        let base = HEADER_SIZE as u64;
+        let pos = match pos {
+            SeekFrom::Start(p) => SeekFrom::Start(p.checked_add(base).ok_or_else(|| {
+                std::io::Error::new(std::io::ErrorKind::InvalidInput, "invalid seek")
+            })?),
+            _ => pos,
+        };
+        self.cursor =
+            self.reader.seek(pos)?.checked_sub(base).ok_or_else(|| {
+                std::io::Error::new(std::io::ErrorKind::InvalidInput, "invalid seek")
+            })?;
+        Ok(self.cursor)
+    }
+}
+
+impl<R> Dump for XmDump<R>
+where
+    R: Read + Seek,
+{
+    fn get_audio_format(&mut self) -> Result<Option<AudioFormat>> {
+        Ok(Some(self.format))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::io::Cursor;
+
+    use anyhow::Result;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_format_ok() {
+        assert_eq!(parse_format(b" MP3"), Some(AudioFormat::Mp3));
+        assert_eq!(parse_format(b" A4M"), Some(AudioFormat::M4a));
+        assert_eq!(parse_format(b" WAV"), Some(AudioFormat::Wav));
+        assert_eq!(parse_format(b"FLAC"), Some(AudioFormat::Flac));
+        assert_eq!(parse_format(b"OGG "), None);
+    }
+
+    #[test]
+    fn test_xmdump_get_data_ok() -> Result<()> {
+        let mut xm = XmDump::from_reader(File::open("res/test.xm")?)?;
+        assert_eq!(xm.get_format(), "flac");
+        let data = xm.get_data()?;
+        let size = std::fs::metadata("res/test.xm")?.len() as usize;
+        assert_eq!(data.len(), size - HEADER_SIZE);
+        assert!(data.starts_with(b"fLaC"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_xmdump_read_ok() -> Result<()> {
+        let mut input = b"ifmt MP3\xFE\xFE\xFE\xFE\x02\x00\x00\x10".to_vec();
+        input.extend_from_slice(&[0x49, 0x44, 0xDC, 0xFF]);
+        let mut xm = XmDump::from_reader(Cursor::new(input))?;
+        assert_eq!(xm.get_format(), "mp3");
+
+        let mut buf = [0; 4];
+        let size = xm.read(&mut buf)?;
+        assert_eq!(size, 4);
+        assert_eq!(buf, [0x49, 0x44, 0x33, 0x10]);
+        assert!(xm.seek(SeekFrom::Current(-8)).is_err());
+        assert!(xm.seek(SeekFrom::Start(u64::MAX)).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_xmdump_err() {
+        let result = XmDump::from_reader(Cursor::new(b"ifmt MP3\x00\x00\x00\x00\x00\x00\x00\x00"));
+        assert!(matches!(result, Err(Errors::InvalidFileType)));
+
+        let result = XmDump::from_reader(Cursor::new(b"ifmtOGG \xFE\xFE\xFE\xFE\x00\x00\x00\x00"));
+        assert!(matches!(result, Err(Errors::InvalidFileType)));
+    }
+}
@@ -0,0 +1,231 @@
+use std::io::{Read, Seek, SeekFrom, Write};
+
+use crate::dump::Dump;
+use crate::error::{Errors, Result};
+
+const HEADER_SIZE: usize = 1024;
+
+const X2M_SCRAMBLE_INIT: f64 = 0.615243;
+const X2M_SCRAMBLE_STEP: f64 = 3.837465;
+const X2M_KEY: &[u8] = b"xmly";
+
+const X3M_SCRAMBLE_INIT: f64 = 0.726354;
+const X3M_SCRAMBLE_STEP: f64 = 3.948737;
+const X3M_KEY: &[u8] = b"3989d111aad5613940f4fc44b639b292";
+
+/// The kind of ximalaya file, which can't be told by the header.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum XmlyKind {
+    /// The x2m file.
+    X2m,
+    /// The x3m file.
+    X3m,
+}
+
+impl XmlyKind {
+    fn scramble(&self) -> (f64, f64, &'static [u8]) {
+        match self {
+            XmlyKind::X2m => (X2M_SCRAMBLE_INIT, X2M_SCRAMBLE_STEP, X2M_KEY),
+            XmlyKind::X3m => (X3M_SCRAMBLE_INIT, X3M_SCRAMBLE_STEP, X3M_KEY),
+        }
+    }
+}
+
+/// The x2m and x3m file dump wrapper.
+///
+/// Only the first 1024 bytes are scrambled, the rest of file is copied directly.
+pub struct XmlyDump<S>
+where
+    S: Read,
+{
+    reader: S,
+    cursor: u64,
+    header: [u8; HEADER_SIZE],
+}
+
+impl<S> XmlyDump<S>
+where
+    S: Read,
+{
+    /// Build the scramble table by sorting the sequence of logistic map.
+    fn build_scramble_table(init: f64, step: f64) -> Vec<usize> {
+        let mut values = Vec::with_capacity(HEADER_SIZE);
+        let mut value = init;
+        for _ in 0..HEADER_SIZE {
+            values.push(value);
+            value = step * value * (1.0 - value);
+        }
+
+        let mut table = (0..HEADER_SIZE).collect::<Vec<usize>>();
+        table.sort_by(|&a, &b| values[a].total_cmp(&values[b]));
+        table
+    }
+
+    fn decrypt_header(kind: XmlyKind, buffer: &[u8; HEADER_SIZE]) -> [u8; HEADER_SIZE] {
+        let (init, step, key) = kind.scramble();
+        let table = Self::build_scramble_table(init, step);
+        let mut header = [0; HEADER_SIZE];
+        for (index, byte) in header.iter_mut().enumerate() {
+            *byte = buffer[table[index]] ^ key[index % key.len()];
+        }
+        header
+    }
+
+    /// Create XmlyDump from reader, the kind of file is usually told by the extension.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use std::fs::File;
+    /// #
+    /// # use ncmdump::{XmlyDump, XmlyKind};
+    /// #
+    /// let file = File::open("res/test.x2m").expect("Can't open file");
+    /// let _ = XmlyDump::from_reader(file, XmlyKind::X2m).unwrap();
+    /// ```
+    pub fn from_reader(mut reader: S, kind: XmlyKind) -> Result<Self> {
+        let mut buffer = [0; HEADER_SIZE];
+        reader
+            .read_exact(&mut buffer)
+            .map_err(|_| Errors::InvalidFileType)?;
+        Ok(Self {
+            reader,
+            cursor: 0,
+            header: Self::decrypt_header(kind, &buffer),
+        })
+    }
+
+    /// Get the music data from xmlydump.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use std::fs::File;
+    /// use std::io::Write;
+    ///
+    /// use anyhow::Result;
+    /// use ncmdump::{XmlyDump, XmlyKind};
+    ///
+    /// fn main() -> Result<()> {
+    ///     let file = File::open("res/test.x3m")?;
+    ///     let mut xmly = XmlyDump::from_reader(file, XmlyKind::X3m)?;
+    ///     let music = xmly.get_data()?;
+    ///
+    ///     let mut target = File::options()
+    ///         .create(true)
+    ///         .write(true)
+    ///         .open("res/test.flac")?;
+    ///     target.write_all(&music)?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn get_data(&mut self) -> std::io::Result<Vec<u8>> {
+        let mut output = Vec::new();
+        if self.cursor < HEADER_SIZE as u64 {
+            output.write_all(&self.header[self.cursor as usize..])?;
+        }
+        self.reader.read_to_end(&mut output)?;
+        self.cursor += output.len() as u64;
+        Ok(output)
+    }
+}
+
+impl<R> Read for XmlyDump<R>
+where
+    R: Read,
+{
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let size = if self.cursor < HEADER_SIZE as u64 {
+            let header = &self.header[self.cursor as usize..];
+            let size = buf.len().min(header.len());
+            buf[..size].copy_from_slice(&header[..size]);
+            size
+        } else {
+            self.reader.read(buf)?
+        };
+        self.cursor += size as u64;
+        Ok(size)
+    }
+}
+
+impl<R> Seek for XmlyDump<R>
+where
+    R: Read + Seek,
+{
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        let position = match pos {
+            SeekFrom::Start(p) => p,
+            SeekFrom::Current(p) => self.cursor.checked_add_signed(p).ok_or_else(|| {
+                std::io::Error::new(std::io::ErrorKind::InvalidInput, "invalid seek")
+            })?,
+            SeekFrom::End(_) => self.reader.seek(pos)?,
+        };
+
+        // The reader is kept after the header, which is read from memory
+        self.reader
+            .seek(SeekFrom::Start(position.max(HEADER_SIZE as u64)))?;
+        self.cursor = position;
+        Ok(self.cursor)
+    }
+}
+
+impl<R> Dump for XmlyDump<R> where R: Read + Seek {}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::io::Cursor;
+
+    use anyhow::Result;
+
+    use super::*;
+
+    #[test]
+    fn test_build_scramble_table_ok() {
+        let table = XmlyDump::<File>::build_scramble_table(X2M_SCRAMBLE_INIT, X2M_SCRAMBLE_STEP);
+        assert_eq!(table[..8], [12, 18, 24, 30, 36, 42, 48, 54]);
+        let table = XmlyDump::<File>::build_scramble_table(X3M_SCRAMBLE_INIT, X3M_SCRAMBLE_STEP);
+        assert_eq!(table[..8], [116, 581, 145, 270, 308, 787, 24, 842]);
+
+        let mut sorted = table.clone();
+        sorted.sort();
+        assert_eq!(sorted, (0..HEADER_SIZE).collect::<Vec<usize>>());
+    }
+
+    #[test]
+    fn test_xmlydump_get_data_ok() -> Result<()> {
+        let mut x2m = XmlyDump::from_reader(File::open("res/test.x2m")?, XmlyKind::X2m)?;
+        let expect = x2m.get_data()?;
+        assert!(expect.starts_with(b"fLaC"));
+        assert_eq!(x2m.stream_position()?, expect.len() as u64);
+
+        // The x2m and x3m file are scrambled from the same music
+        let mut x3m = XmlyDump::from_reader(File::open("res/test.x3m")?, XmlyKind::X3m)?;
+        assert_eq!(x3m.get_data()?, expect);
+        assert_eq!(x3m.stream_position()?, expect.len() as u64);
+        Ok(())
+    }
+
+    #[test]
+    fn test_xmlydump_read_seek_ok() -> Result<()> {
+        let expect =
+            XmlyDump::from_reader(File::open("res/test.x3m")?, XmlyKind::X3m)?.get_data()?;
+
+        let mut x2m = XmlyDump::from_reader(File::open("res/test.x2m")?, XmlyKind::X2m)?;
+        let mut buf = [0; 16];
+        x2m.seek(SeekFrom::Start(1016))?;
+        x2m.read_exact(&mut buf)?;
+        assert_eq!(buf, expect[1016..1032]);
+
+        x2m.seek(SeekFrom::Current(-32))?;
+        x2m.read_exact(&mut buf)?;
+        assert_eq!(buf, expect[1000..1016]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_xmlydump_err() {
+        let result = XmlyDump::from_reader(Cursor::new([0; 16]), XmlyKind::X2m);
+        assert!(matches!(result, Err(Errors::InvalidFileType)));
+    }
+}